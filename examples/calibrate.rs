@@ -0,0 +1,59 @@
+#![no_main]
+#![no_std]
+
+use embedded_hal::digital::v2::*;
+use embedded_hal::prelude::*;
+use msp430_rt::entry;
+use msp430fr247x_hal::{
+    capture::{measure::Measure, CaptureParts7, TimerConfig},
+    clock::{ClockConfig, DcoclkFreqSel, MclkDiv, SmclkDiv},
+    fram::Fram,
+    gpio::Batch,
+    pmm::Pmm,
+    watchdog::Wdt,
+};
+use nb::block;
+use panic_msp430 as _;
+use void::ResultVoidExt;
+
+// Measure the actual VLOCLK frequency against the 8 MHz SMCLK, then toggle P1.0 every ~256 ms
+// using the watchdog interval timer running off the calibrated ACLK
+#[entry]
+fn main() -> ! {
+    let periph = msp430fr247x::Peripherals::take().unwrap();
+
+    let mut fram = Fram::new(periph.FRCTL);
+    let mut wdt = Wdt::constrain(periph.WDT_A).to_interval();
+
+    let pmm = Pmm::new(periph.PMM);
+    let p1 = Batch::new(periph.P1)
+        .config_pin0(|p| p.to_output())
+        .split(&pmm);
+    let mut p1_0 = p1.pin0;
+
+    let (smclk, mut aclk) = ClockConfig::new(periph.CS)
+        .mclk_dcoclk(DcoclkFreqSel::_8MHz, MclkDiv::_1)
+        .smclk_on(SmclkDiv::_1)
+        .aclk_vloclk()
+        .freeze(&mut fram);
+
+    let captures = CaptureParts7::config(periph.TB0, TimerConfig::smclk(&smclk))
+        .config_cap0_input_aclk(&aclk)
+        .commit();
+    let mut measure = Measure::new(captures.cap0);
+    aclk.calibrate(&mut measure, 16).ok();
+
+    wdt.set_aclk(&aclk).set_period(256).ok();
+    loop {
+        block!(wdt.wait()).void_unwrap();
+        p1_0.toggle().void_unwrap();
+    }
+}
+
+// The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
+// enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
+// so for now, we create our own in each application where debug assertions are present.
+#[no_mangle]
+extern "C" fn abort() -> ! {
+    panic!();
+}
@@ -2,12 +2,9 @@
 #![no_std]
 #![feature(abi_msp430_interrupt)]
 
-use core::cell::UnsafeCell;
-use critical_section::with;
 use embedded_hal::digital::v2::ToggleableOutputPin;
-use msp430::interrupt::{enable, Mutex};
+use msp430::interrupt::enable;
 use msp430_rt::entry;
-use msp430fr247x::interrupt;
 use msp430fr247x_hal::{
     capture::{
         CapCmp, CapTrigger, Capture, CaptureParts7, CaptureVector, TBxIV, TimerConfig, CCR1,
@@ -16,6 +13,8 @@ use msp430fr247x_hal::{
     fram::Fram,
     gpio::Batch,
     gpio::*,
+    irq::IrqSlot,
+    irq_handler,
     pmm::Pmm,
     watchdog::Wdt,
 };
@@ -27,12 +26,9 @@ use panic_msp430 as _;
 #[cfg(not(debug_assertions))]
 use panic_never as _;
 
-static CAPTURE: Mutex<UnsafeCell<Option<Capture<msp430fr247x::TB0, CCR1>>>> =
-    Mutex::new(UnsafeCell::new(None));
-static VECTOR: Mutex<UnsafeCell<Option<TBxIV<msp430fr247x::TB0>>>> =
-    Mutex::new(UnsafeCell::new(None));
-static RED_LED: Mutex<UnsafeCell<Option<Pin<P1, Pin0, Output>>>> =
-    Mutex::new(UnsafeCell::new(None));
+static CAPTURE: IrqSlot<Capture<msp430fr247x::TB0, CCR1>> = IrqSlot::new();
+static VECTOR: IrqSlot<TBxIV<msp430fr247x::TB0>> = IrqSlot::new();
+static RED_LED: IrqSlot<Pin<P1, Pin0, Output>> = IrqSlot::new();
 
 // Connect push button input to P1.6. When button is pressed, red LED should toggle. No debouncing,
 // so sometimes inputs are missed.
@@ -49,7 +45,7 @@ fn main() -> ! {
             .split(&pmm);
         let red_led = p1.pin0;
 
-        with(|cs| unsafe { *RED_LED.borrow(cs).get() = Some(red_led) });
+        RED_LED.install(red_led);
 
         let (_smclk, aclk) = ClockConfig::new(periph.CS)
             .mclk_dcoclk(DcoclkFreqSel::_1MHz, MclkDiv::_1)
@@ -65,10 +61,8 @@ fn main() -> ! {
         let vectors = captures.tbxiv;
 
         setup_capture(&mut capture);
-        with(|cs| {
-            unsafe { *CAPTURE.borrow(cs).get() = Some(capture) }
-            unsafe { *VECTOR.borrow(cs).get() = Some(vectors) }
-        });
+        CAPTURE.install(capture);
+        VECTOR.install(vectors);
         unsafe { enable() };
     }
 
@@ -79,25 +73,13 @@ fn setup_capture<T: CapCmp<C>, C>(capture: &mut Capture<T, C>) {
     capture.enable_interrupts();
 }
 
-#[interrupt]
-fn TIMER0_B1() {
-    with(|cs| {
-        if let Some(vector) = unsafe { &mut *VECTOR.borrow(cs).get() }.as_mut() {
-            if let Some(capture) = unsafe { &mut *CAPTURE.borrow(cs).get() }.as_mut() {
-                match vector.interrupt_vector() {
-                    CaptureVector::Capture1(cap) => {
-                        if cap.interrupt_capture(capture).is_ok() {
-                            if let Some(led) = unsafe { &mut *RED_LED.borrow(cs).get() }.as_mut() {
-                                led.toggle().void_unwrap();
-                            }
-                        }
-                    }
-                    _ => {}
-                };
-            }
+irq_handler!(TIMER0_B1, |vector: VECTOR, capture: CAPTURE; red_led: RED_LED| {
+    if let CaptureVector::Capture1(cap) = vector.interrupt_vector() {
+        if cap.interrupt_capture(capture).is_ok() {
+            red_led.toggle().void_unwrap();
         }
-    });
-}
+    }
+});
 
 // The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
 // enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
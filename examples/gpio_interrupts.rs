@@ -2,26 +2,23 @@
 #![no_std]
 #![feature(abi_msp430_interrupt)]
 
-use critical_section::with;
-use msp430fr247x::interrupt;
-
-use core::cell::RefCell;
 use embedded_hal::digital::v2::*;
-use embedded_hal::timer::*;
-use msp430::interrupt::{enable as enable_int, Mutex};
+use msp430::interrupt::enable as enable_int;
 use msp430_rt::entry;
 use msp430fr247x_hal::{
     clock::{ClockConfig, MclkDiv, SmclkDiv},
     fram::Fram,
     gpio::{Batch, GpioVector, Output, Pin, Pin0, PxIV, P1, P2},
+    irq::IrqSlot,
+    irq_handler,
     pmm::Pmm,
-    watchdog::{Wdt, WdtClkPeriods},
+    watchdog::Wdt,
 };
 use nb::block;
 use panic_msp430 as _;
 
-static RED_LED: Mutex<RefCell<Option<Pin<P1, Pin0, Output>>>> = Mutex::new(RefCell::new(None));
-static P2IV: Mutex<RefCell<Option<PxIV<P2>>>> = Mutex::new(RefCell::new(None));
+static RED_LED: IrqSlot<Pin<P1, Pin0, Output>> = IrqSlot::new();
+static P2IV: IrqSlot<PxIV<P2>> = IrqSlot::new();
 
 // Red LED should blink 2 seconds on, 2 seconds off
 // Both green and red LEDs should blink when P2.3 LED is pressed
@@ -53,12 +50,13 @@ fn main() -> ! {
     let mut green_led = p6.pin6;
     let p2iv = p2.pxiv;
 
-    with(|cs| *RED_LED.borrow(cs).borrow_mut() = Some(red_led));
-    with(|cs| *P2IV.borrow(cs).borrow_mut() = Some(p2iv));
+    RED_LED.install(red_led);
+    P2IV.install(p2iv);
 
     wdt.set_aclk(&aclk)
         .enable_interrupts()
-        .start(WdtClkPeriods::_32K);
+        .set_period(1000)
+        .ok();
     pin.select_rising_edge_trigger().enable_interrupts();
     button.select_falling_edge_trigger();
 
@@ -71,32 +69,16 @@ fn main() -> ! {
     }
 }
 
-#[interrupt]
-fn PORT2() {
-    with(|cs| {
-        RED_LED.borrow(cs).borrow_mut().as_mut().map(|red_led| {
-            match P2IV
-                .borrow(cs)
-                .borrow_mut()
-                .as_mut()
-                .unwrap()
-                .get_interrupt_vector()
-            {
-                GpioVector::Pin7Isr => red_led.toggle().ok(),
-                _ => panic!(),
-            }
-        })
-    });
-}
+irq_handler!(PORT2, |p2iv: P2IV; red_led: RED_LED| {
+    match p2iv.get_interrupt_vector() {
+        GpioVector::Pin7Isr => red_led.toggle().ok(),
+        _ => panic!(),
+    };
+});
 
-#[interrupt]
-fn WDT() {
-    with(|cs| {
-        RED_LED.borrow(cs).borrow_mut().as_mut().map(|red_led| {
-            red_led.toggle().ok();
-        })
-    });
-}
+irq_handler!(WDT, |; red_led: RED_LED| {
+    red_led.toggle().ok();
+});
 
 // The compiler will emit calls to the abort() compiler intrinsic if debug assertions are
 // enabled (default for dev profile). MSP430 does not actually have meaningful abort() support
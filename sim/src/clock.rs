@@ -0,0 +1,77 @@
+//! Clock objects, in place of the HAL's clock system configuration
+//!
+//! The drivers only use the clock objects for their frequencies, so tests create them directly
+//! with the frequency the clock system would have been configured for.
+
+use core::marker::PhantomData;
+
+pub use crate::calc::{REFOCLK, VLOCLK};
+
+/// ACLK source typestate for the internal low-power, low-accuracy VLOCLK oscillator
+pub struct Vlo;
+/// ACLK source typestate for the internal trimmed REFOCLK oscillator
+pub struct Refo;
+
+/// Source of ACLK
+pub trait AclkSource {
+    /// Nominal frequency of the source
+    const FREQ: u16;
+}
+
+impl AclkSource for Vlo {
+    const FREQ: u16 = VLOCLK;
+}
+
+impl AclkSource for Refo {
+    const FREQ: u16 = REFOCLK;
+}
+
+/// SMCLK clock object
+pub struct Smclk(pub(crate) u32);
+/// ACLK clock object, typed by its source
+pub struct Aclk<SRC: AclkSource = Refo>(pub(crate) u16, PhantomData<SRC>);
+
+impl Smclk {
+    /// SMCLK running at `freq` Hz
+    pub fn new(freq: u32) -> Self {
+        Smclk(freq)
+    }
+}
+
+impl<SRC: AclkSource> Aclk<SRC> {
+    /// ACLK running at the nominal frequency of its source
+    pub fn new() -> Self {
+        Aclk(SRC::FREQ, PhantomData)
+    }
+}
+
+impl<SRC: AclkSource> Default for Aclk<SRC> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Trait for configured clock objects
+pub trait Clock {
+    /// Type of the returned frequency value
+    type Freq;
+
+    /// Frequency of the clock
+    fn freq(&self) -> Self::Freq;
+}
+
+impl Clock for Smclk {
+    type Freq = u32;
+
+    fn freq(&self) -> u32 {
+        self.0
+    }
+}
+
+impl<SRC: AclkSource> Clock for Aclk<SRC> {
+    type Freq = u16;
+
+    fn freq(&self) -> u16 {
+        self.0
+    }
+}
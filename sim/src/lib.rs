@@ -0,0 +1,53 @@
+//! Host test harness for the msp430fr247x-hal drivers.
+//!
+//! The GPIO, button, serial, timer, PWM and capture drivers of the HAL are compiled from their
+//! sources in `../src` against in-memory models of the peripheral registers, instead of the real
+//! peripherals of the PAC. The models implement the same `hw_traits` traits the PAC peripherals
+//! implement, so the drivers run unchanged with `cargo test` on the host, and the tests can inspect
+//! the register state and the sequence of register writes the drivers made through the [`model`]
+//! module. The deadline bookkeeping of the watchdog supervisor, which touches no registers, is
+//! built as well.
+//!
+//! The crate root stands in for the PAC, providing the peripheral types the drivers name, such as
+//! [`P1`], [`TB0`] and [`E_USCI_A0`], as well as [`Peripherals`]. The [`clock`] and [`pmm`]
+//! modules replace the HAL modules of the same name, which configure peripherals not modelled
+//! here.
+
+#![allow(incomplete_features)] // Enable specialization without warnings
+#![feature(specialization)]
+
+// The drivers import the PAC as `msp430fr247x`
+extern crate self as msp430fr247x;
+
+pub mod clock;
+pub mod model;
+mod periph;
+pub mod pmm;
+
+pub use periph::*;
+
+// Some driver items are only used by drivers not built here, and the drivers are linted by the
+// HAL's own build
+#[allow(dead_code, clippy::all)]
+#[path = "../../src"]
+mod hal {
+    pub mod batch_gpio;
+    pub mod button;
+    pub mod calc;
+    pub mod capture;
+    pub mod gpio;
+    pub mod hw_traits;
+    pub mod pwm;
+    pub mod serial;
+    pub mod timer;
+    pub mod util;
+    pub mod watchdog {
+        pub mod supervisor {
+            pub mod deadlines;
+        }
+    }
+}
+
+pub use hal::watchdog::supervisor::deadlines;
+pub use hal::{batch_gpio, button, calc, capture, gpio, pwm, serial, timer};
+use hal::{hw_traits, util};
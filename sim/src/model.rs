@@ -0,0 +1,493 @@
+//! In-memory models of the peripheral registers
+//!
+//! Every test thread has its own [`Model`], which starts out in the reset state of the device and
+//! can be accessed with [`with`]. Register writes made by the drivers, including the
+//! read-modify-write ones, update the model and are appended to its write log, which tests
+//! inspect with [`take_writes`]. Register reads are not logged.
+//!
+//! The models cover the register behaviour the drivers depend on:
+//!
+//! * A port's PxIN reads the levels set in [`Port::input`] for input pins and PxOUT for output
+//!   pins. Reading PxIV returns and clears the lowest-numbered enabled pending interrupt.
+//! * Timer_B counts the ticks passed to [`TimerB::advance`], and one tick after every read of its
+//!   registers, like a timer clock as fast as MCLK, so busy-waits on the timer make progress and
+//!   two consecutive reads of TBxR never agree. The clock source and dividers are ignored.
+//!   Compare matches, counter wraps and captures of the CCI inputs set the interrupt flags, and
+//!   reading TBxIV returns and clears the highest-priority enabled pending interrupt. The counter
+//!   is compared to the compare latches TBxCLn, which load the values written to TBxCCRn on the
+//!   event selected by the CLLD bits. Grouped latches, as selected by TBCLGRP, only load once
+//!   every TBxCCRn of the group has been written since the last load, on the event of the lowest
+//!   register of the group, or of TBxCCR1 when all latches are grouped. The CCI
+//!   inputs aren't connected to the port models, and are driven with [`TimerB::set_cci`] or, for
+//!   measurements, with a [`SquareWave`] advancing on every tick.
+//! * The eUSCI_A modules transmit a byte as soon as it is written to UCAxTXBUF, which appends it
+//!   to [`EUsciA::sent`] and, with UCLISTEN set, receives it back. [`EUsciA::receive`] receives a
+//!   byte from the outside.
+
+use std::cell::RefCell;
+use std::vec::Vec;
+
+/// A logged register write: the register name, as spelled in the user's guide, and the value
+/// written to it
+pub type Write = (&'static str, u16);
+
+thread_local! {
+    static MODEL: RefCell<Model> = RefCell::new(Model::default());
+}
+
+/// Run `f` with the register model of the current thread
+pub fn with<R>(f: impl FnOnce(&mut Model) -> R) -> R {
+    MODEL.with(|m| f(&mut m.borrow_mut()))
+}
+
+/// Return the register writes logged since the last call, clearing the log
+pub fn take_writes() -> Vec<Write> {
+    with(|m| core::mem::take(&mut m.writes))
+}
+
+/// Return the register writes logged since the last call that went to registers whose names start
+/// with `prefix`, clearing the log
+pub fn take_writes_to(prefix: &str) -> Vec<Write> {
+    take_writes()
+        .into_iter()
+        .filter(|(reg, _)| reg.starts_with(prefix))
+        .collect()
+}
+
+/// Put the register model of the current thread back into the reset state
+pub fn reset() {
+    with(|m| *m = Model::default());
+}
+
+/// Register state of the modelled peripherals
+pub struct Model {
+    /// Digital I/O ports 1 to 6
+    pub ports: [Port; 6],
+    /// Timer TB0
+    pub tb0: TimerB,
+    /// eUSCI_A0 and eUSCI_A1
+    pub usci: [EUsciA; 2],
+    /// LOCKLPM5 bit of PM5CTL0, which locks the I/O configuration until the PMM is set up
+    pub locklpm5: bool,
+    writes: Vec<Write>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model {
+            ports: Default::default(),
+            tb0: TimerB::default(),
+            usci: Default::default(),
+            locklpm5: true,
+            writes: Vec::new(),
+        }
+    }
+}
+
+impl Model {
+    /// Append a register write to the write log
+    pub fn log(&mut self, reg: &'static str, value: u16) {
+        self.writes.push((reg, value));
+    }
+}
+
+/// Registers of a digital I/O port
+#[derive(Default)]
+pub struct Port {
+    /// Levels applied to the pins from the outside
+    pub input: u8,
+    pub out: u8,
+    pub dir: u8,
+    pub ren: u8,
+    pub sel0: u8,
+    pub sel1: u8,
+    pub ies: u8,
+    pub ie: u8,
+    pub ifg: u8,
+}
+
+impl Port {
+    /// Value of PxIN
+    pub fn pxin(&self) -> u8 {
+        (self.input & !self.dir) | (self.out & self.dir)
+    }
+
+    pub(crate) fn take_iv(&mut self) -> u16 {
+        let pending = self.ifg & self.ie;
+        if pending == 0 {
+            return 0;
+        }
+        let pin = pending.trailing_zeros();
+        self.ifg &= !(1 << pin);
+        2 * (pin as u16 + 1)
+    }
+}
+
+// TBxCTL bits
+pub const TBCLGRP: u16 = 0b11 << 13;
+pub const CNTL: u16 = 0b11 << 11;
+pub const TBSSEL: u16 = 0b11 << 8;
+pub const ID: u16 = 0b11 << 6;
+pub const MC: u16 = 0b11 << 4;
+pub const TBCLR: u16 = 1 << 2;
+pub const TBIE: u16 = 1 << 1;
+pub const TBIFG: u16 = 1;
+
+// TBxCCTLn bits
+pub const CM: u16 = 0b11 << 14;
+pub const CCIS: u16 = 0b11 << 12;
+pub const SCS: u16 = 1 << 11;
+pub const CLLD: u16 = 0b11 << 9;
+pub const CAP: u16 = 1 << 8;
+pub const OUTMOD: u16 = 0b111 << 5;
+pub const CCIE: u16 = 1 << 4;
+pub const CCI: u16 = 1 << 3;
+pub const OUT: u16 = 1 << 2;
+pub const COV: u16 = 1 << 1;
+pub const CCIFG: u16 = 1;
+
+/// Registers of a Timer_B with 7 capture-compare registers
+#[derive(Default)]
+pub struct TimerB {
+    pub ctl: u16,
+    pub r: u16,
+    pub ex0: u16,
+    /// TBxCCTLn, without the CCI bit, which is derived from the selected capture input
+    pub cctl: [u16; 7],
+    pub ccr: [u16; 7],
+    /// Compare latches TBxCLn
+    pub cl: [u16; 7],
+    /// Whether TBxCCRn was written since its latch was last loaded
+    written: [bool; 7],
+    /// Levels of the CCInA capture inputs
+    pub cci_a: [bool; 7],
+    /// Levels of the CCInB capture inputs
+    pub cci_b: [bool; 7],
+    /// Square waves driving the CCInA and CCInB capture inputs, with the ticks elapsed in their
+    /// current period
+    waves: [[Option<(SquareWave, u32)>; 2]; 7],
+    counting_down: bool,
+}
+
+/// Capture input of a capture-compare register
+#[derive(Clone, Copy)]
+pub enum CciInput {
+    /// CCInA, usually a GPIO pin
+    A,
+    /// CCInB, usually an internal signal
+    B,
+}
+
+/// Periodic signal applied to a capture input, which is high for the first `high` of every
+/// `period` timer ticks
+#[derive(Clone, Copy)]
+pub struct SquareWave {
+    pub period: u32,
+    pub high: u32,
+}
+
+impl TimerB {
+    /// Largest counter value for the configured counter length
+    pub fn max(&self) -> u16 {
+        match (self.ctl & CNTL) >> 11 {
+            0 => 0xFFFF,
+            1 => 0x0FFF,
+            2 => 0x03FF,
+            _ => 0x00FF,
+        }
+    }
+
+    /// Mode control bits, 0 for stop, 1 for up, 2 for continuous and 3 for up/down mode
+    pub fn mc(&self) -> u16 {
+        (self.ctl & MC) >> 4
+    }
+
+    /// Value of TBxCCTLn
+    pub fn cctl_rd(&self, n: usize) -> u16 {
+        self.cctl[n] | if self.cci(n) { CCI } else { 0 }
+    }
+
+    /// Level of the capture input selected for capture-compare register `n`
+    pub fn cci(&self, n: usize) -> bool {
+        match (self.cctl[n] & CCIS) >> 12 {
+            0 => self.cci_a[n],
+            1 => self.cci_b[n],
+            2 => false,
+            _ => true,
+        }
+    }
+
+    /// Count `ticks` timer clock ticks
+    pub fn advance(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Drive a capture input of capture-compare register `n` to a constant level
+    pub fn set_cci(&mut self, n: usize, input: CciInput, level: bool) {
+        self.waves[n][input as usize] = None;
+        self.drive_cci(n, input, level);
+    }
+
+    /// Drive a capture input of capture-compare register `n` with a square wave, starting at the
+    /// beginning of its high phase. The wave keeps running while the timer is stopped.
+    pub fn set_cci_wave(&mut self, n: usize, input: CciInput, wave: SquareWave) {
+        self.waves[n][input as usize] = Some((wave, 0));
+        self.drive_cci(n, input, wave.high > 0);
+    }
+
+    fn drive_cci(&mut self, n: usize, input: CciInput, level: bool) {
+        let old = self.cci(n);
+        match input {
+            CciInput::A => self.cci_a[n] = level,
+            CciInput::B => self.cci_b[n] = level,
+        }
+        self.input_changed(n, old);
+    }
+
+    pub(crate) fn ctl_wr(&mut self, value: u16) {
+        if value & TBCLR != 0 {
+            self.r = 0;
+            self.counting_down = false;
+        }
+        self.ctl = value & !TBCLR;
+    }
+
+    pub(crate) fn ccr_wr(&mut self, n: usize, value: u16) {
+        self.ccr[n] = value;
+        self.written[n] = true;
+        let (group, ctl) = self.latch_group(n);
+        if (self.cctl[ctl] & CLLD) >> 9 == 0 {
+            self.load_latches(group);
+        }
+    }
+
+    pub(crate) fn cctl_wr(&mut self, n: usize, value: u16) {
+        let old = self.cci(n);
+        self.cctl[n] = value & !CCI;
+        self.input_changed(n, old);
+    }
+
+    // Read a register, counting a tick afterwards
+    pub(crate) fn rd<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let value = f(self);
+        self.tick();
+        value
+    }
+
+    pub(crate) fn take_iv(&mut self) -> u16 {
+        for n in 1..7 {
+            if self.cctl[n] & (CCIE | CCIFG) == CCIE | CCIFG {
+                self.cctl[n] &= !CCIFG;
+                return 2 * n as u16;
+            }
+        }
+        if self.ctl & (TBIE | TBIFG) == TBIE | TBIFG {
+            self.ctl &= !TBIFG;
+            return 14;
+        }
+        0
+    }
+
+    fn tick(&mut self) {
+        self.count();
+        for n in 0..7 {
+            for input in [CciInput::A, CciInput::B] {
+                if let Some((wave, elapsed)) = &mut self.waves[n][input as usize] {
+                    *elapsed = (*elapsed + 1) % wave.period;
+                    let level = *elapsed < wave.high;
+                    self.drive_cci(n, input, level);
+                }
+            }
+        }
+    }
+
+    // Registers grouped with register `n`, and the register whose CLLD bits control the group
+    fn latch_group(&self, n: usize) -> (core::ops::Range<usize>, usize) {
+        let group = match ((self.ctl & TBCLGRP) >> 13, n) {
+            (0, _) | (1 | 2, 0) => n..n + 1,
+            (1, _) => (n - 1) / 2 * 2 + 1..(n - 1) / 2 * 2 + 3,
+            (2, 1..=3) => 1..4,
+            (2, _) => 4..7,
+            _ => return (0..7, 1),
+        };
+        let ctl = group.start;
+        (group, ctl)
+    }
+
+    // Load the latches of a group if all of its registers were written
+    fn load_latches(&mut self, group: core::ops::Range<usize>) {
+        if self.written[group.clone()].iter().all(|&w| w) {
+            for n in group {
+                self.cl[n] = self.ccr[n];
+                self.written[n] = false;
+            }
+        }
+    }
+
+    // Load the latches whose load event happened on the last count
+    fn load_event(&mut self) {
+        let mut n = 0;
+        while n < 7 {
+            let (group, ctl) = self.latch_group(n);
+            n = group.end;
+            let event = match (self.cctl[ctl] & CLLD) >> 9 {
+                0 => false,
+                1 => self.r == 0,
+                2 => self.r == 0 || (self.mc() == 3 && self.r == self.cl[0]),
+                _ => self.r == self.cl[ctl],
+            };
+            if event {
+                self.load_latches(group);
+            }
+        }
+    }
+
+    fn count(&mut self) {
+        let top = match self.mc() {
+            0 => return,
+            2 => self.max(),
+            _ => self.cl[0],
+        };
+        if self.mc() == 3 {
+            // Up/down mode counts up to TBxCCR0 and back down to 0, flagging TBIFG at 0
+            if top == 0 {
+                return;
+            }
+            if self.counting_down || self.r >= top {
+                self.r -= 1;
+                self.counting_down = self.r > 0;
+                if self.r == 0 {
+                    self.ctl |= TBIFG;
+                }
+            } else {
+                self.r += 1;
+            }
+        } else if self.r >= top {
+            self.r = 0;
+            self.ctl |= TBIFG;
+        } else {
+            self.r += 1;
+        }
+
+        self.load_event();
+        for n in 0..7 {
+            if self.cctl[n] & CAP == 0 && self.r == self.cl[n] {
+                self.cctl[n] |= CCIFG;
+            }
+        }
+    }
+
+    fn input_changed(&mut self, n: usize, old: bool) {
+        let new = self.cci(n);
+        let cm = (self.cctl[n] & CM) >> 14;
+        let edge = match (old, new) {
+            (false, true) => cm & 0b01 != 0,
+            (true, false) => cm & 0b10 != 0,
+            _ => false,
+        };
+        if self.cctl[n] & CAP != 0 && edge {
+            if self.cctl[n] & CCIFG != 0 {
+                self.cctl[n] |= COV;
+            }
+            self.ccr[n] = self.r;
+            self.cctl[n] |= CCIFG;
+        }
+    }
+}
+
+// UCAxCTLW0 bits
+pub const UCSWRST: u16 = 1;
+
+// UCAxSTATW bits
+pub const UCLISTEN: u16 = 1 << 7;
+pub const UCFE: u16 = 1 << 6;
+pub const UCOE: u16 = 1 << 5;
+pub const UCPE: u16 = 1 << 4;
+pub const UCBRK: u16 = 1 << 3;
+pub const UCRXERR: u16 = 1 << 2;
+pub const UCBUSY: u16 = 1;
+
+// UCAxIE and UCAxIFG bits
+pub const UCTXIFG: u16 = 1 << 1;
+pub const UCRXIFG: u16 = 1;
+
+/// Registers of an eUSCI_A module in UART mode
+pub struct EUsciA {
+    pub ctlw0: u16,
+    pub brw: u16,
+    pub mctlw: u16,
+    pub statw: u16,
+    pub rxbuf: u16,
+    pub ie: u16,
+    pub ifg: u16,
+    /// Bytes transmitted so far
+    pub sent: Vec<u8>,
+}
+
+impl Default for EUsciA {
+    fn default() -> Self {
+        EUsciA {
+            ctlw0: UCSWRST,
+            brw: 0,
+            mctlw: 0,
+            statw: 0,
+            rxbuf: 0,
+            ie: 0,
+            ifg: UCTXIFG,
+            sent: Vec::new(),
+        }
+    }
+}
+
+impl EUsciA {
+    /// Receive a byte, flagging an overrun if the previous byte was not read yet
+    pub fn receive(&mut self, byte: u8) {
+        if self.ctlw0 & UCSWRST != 0 {
+            return;
+        }
+        if self.ifg & UCRXIFG != 0 {
+            self.statw |= UCOE;
+        }
+        self.rxbuf = byte as u16;
+        self.ifg |= UCRXIFG;
+    }
+
+    pub(crate) fn ctlw0_wr(&mut self, value: u16) {
+        if value & UCSWRST != 0 {
+            self.ie = 0;
+            self.ifg = UCTXIFG;
+            self.statw &= UCLISTEN;
+        }
+        self.ctlw0 = value;
+    }
+
+    pub(crate) fn txbuf_wr(&mut self, byte: u8) {
+        if self.ctlw0 & UCSWRST != 0 {
+            return;
+        }
+        self.sent.push(byte);
+        if self.statw & UCLISTEN != 0 {
+            self.receive(byte);
+        }
+    }
+
+    pub(crate) fn rxbuf_rd(&mut self) -> u8 {
+        self.ifg &= !UCRXIFG;
+        self.statw &= !(UCFE | UCOE | UCPE | UCBRK | UCRXERR);
+        self.rxbuf as u8
+    }
+
+    pub(crate) fn take_iv(&mut self) -> u16 {
+        if self.ie & self.ifg & UCRXIFG != 0 {
+            self.ifg &= !UCRXIFG;
+            2
+        } else if self.ie & self.ifg & UCTXIFG != 0 {
+            self.ifg &= !UCTXIFG;
+            4
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,534 @@
+// Peripheral types standing in for the PAC ones, implementing the `hw_traits` traits on top of the
+// register models
+
+use crate::hw_traits::eusci::{EUsci, EUsciUart, UcaxStatw, UcxCtl0};
+use crate::hw_traits::gpio::{GpioPeriph, IntrPeriph, PortId};
+use crate::hw_traits::timerb::*;
+use crate::hw_traits::Steal;
+use crate::model::{
+    self, Port, CAP, CCIE, CCIFG, CCIS, CLLD, CM, COV, ID, MC, OUT, SCS, TBCLR, TBIE, TBIFG, UCBRK,
+    UCBUSY, UCFE, UCLISTEN, UCOE, UCPE, UCRXIFG, UCSWRST, UCTXIFG,
+};
+use core::marker::PhantomData;
+
+macro_rules! peripherals {
+    ($($(#[$meta:meta])* $PERIPH:ident),*) => {
+        $(
+            $(#[$meta])*
+            #[allow(non_camel_case_types)]
+            pub struct $PERIPH {
+                _marker: PhantomData<*const ()>,
+            }
+
+            impl Steal for $PERIPH {
+                unsafe fn steal() -> Self {
+                    $PERIPH { _marker: PhantomData }
+                }
+            }
+        )*
+
+        /// All the modelled peripherals
+        #[allow(non_snake_case)]
+        pub struct Peripherals {
+            $(
+                $(#[$meta])*
+                pub $PERIPH: $PERIPH,
+            )*
+        }
+
+        impl Peripherals {
+            /// Returns the peripherals, after putting the register model of the current thread
+            /// back into the reset state
+            pub fn take() -> Option<Self> {
+                model::reset();
+                Some(Peripherals {
+                    $($PERIPH: $PERIPH { _marker: PhantomData },)*
+                })
+            }
+        }
+    };
+}
+
+peripherals!(
+    /// Port 1
+    P1,
+    /// Port 2
+    P2,
+    /// Port 3
+    P3,
+    /// Port 4
+    P4,
+    /// Port 5
+    P5,
+    /// Port 6
+    P6,
+    /// Timer TB0
+    TB0,
+    /// eUSCI_A0
+    E_USCI_A0,
+    /// eUSCI_A1
+    E_USCI_A1,
+    /// Power management module
+    PMM
+);
+
+macro_rules! port_reg {
+    ($px:literal, $reg:literal, $field:ident, $rd:ident, $wr:ident, $set:ident, $clear:ident) => {
+        fn $rd(&self) -> u8 {
+            model::with(|m| m.ports[Self::ID as usize].$field)
+        }
+
+        fn $wr(&self, bits: u8) {
+            self.port_wr(concat!($px, $reg), |p| &mut p.$field, |_| bits);
+        }
+
+        fn $set(&self, bits: u8) {
+            self.port_wr(concat!($px, $reg), |p| &mut p.$field, |r| r | bits);
+        }
+
+        // Takes the inverted mask, so only the zero bits are cleared
+        fn $clear(&self, bits: u8) {
+            self.port_wr(concat!($px, $reg), |p| &mut p.$field, |r| r & bits);
+        }
+    };
+}
+
+macro_rules! port_impl {
+    ($Px:ident, $px:literal) => {
+        impl $Px {
+            fn port_wr(
+                &self,
+                reg: &'static str,
+                field: fn(&mut Port) -> &mut u8,
+                f: impl FnOnce(u8) -> u8,
+            ) {
+                model::with(|m| {
+                    let field = field(&mut m.ports[Self::ID as usize]);
+                    *field = f(*field);
+                    let value = *field;
+                    m.log(reg, value as u16);
+                });
+            }
+        }
+
+        impl GpioPeriph for $Px {
+            const ID: PortId = PortId::$Px;
+
+            fn pxin_rd(&self) -> u8 {
+                model::with(|m| m.ports[Self::ID as usize].pxin())
+            }
+
+            fn pxselc_wr(&self, bits: u8) {
+                model::with(|m| {
+                    let port = &mut m.ports[Self::ID as usize];
+                    port.sel0 ^= bits;
+                    port.sel1 ^= bits;
+                    m.log(concat!($px, "SELC"), bits as u16);
+                });
+            }
+
+            fn pxout_toggle(&self, bits: u8) {
+                self.port_wr(concat!($px, "OUT"), |p| &mut p.out, |r| r ^ bits);
+            }
+
+            port_reg!($px, "OUT", out, pxout_rd, pxout_wr, pxout_set, pxout_clear);
+            port_reg!($px, "DIR", dir, pxdir_rd, pxdir_wr, pxdir_set, pxdir_clear);
+            port_reg!($px, "REN", ren, pxren_rd, pxren_wr, pxren_set, pxren_clear);
+            port_reg!(
+                $px,
+                "SEL0",
+                sel0,
+                pxsel0_rd,
+                pxsel0_wr,
+                pxsel0_set,
+                pxsel0_clear
+            );
+            port_reg!(
+                $px,
+                "SEL1",
+                sel1,
+                pxsel1_rd,
+                pxsel1_wr,
+                pxsel1_set,
+                pxsel1_clear
+            );
+        }
+    };
+}
+
+macro_rules! intr_port_impl {
+    ($Px:ident, $px:literal) => {
+        impl IntrPeriph for $Px {
+            port_reg!($px, "IES", ies, pxies_rd, pxies_wr, pxies_set, pxies_clear);
+            port_reg!($px, "IE", ie, pxie_rd, pxie_wr, pxie_set, pxie_clear);
+            port_reg!($px, "IFG", ifg, pxifg_rd, pxifg_wr, pxifg_set, pxifg_clear);
+
+            fn pxiv_rd(&self) -> u16 {
+                model::with(|m| m.ports[Self::ID as usize].take_iv())
+            }
+        }
+    };
+}
+
+port_impl!(P1, "P1");
+port_impl!(P2, "P2");
+port_impl!(P3, "P3");
+port_impl!(P4, "P4");
+port_impl!(P5, "P5");
+port_impl!(P6, "P6");
+
+intr_port_impl!(P1, "P1");
+intr_port_impl!(P2, "P2");
+intr_port_impl!(P3, "P3");
+intr_port_impl!(P4, "P4");
+
+fn tb0_rd<R>(f: impl FnOnce(&mut model::TimerB) -> R) -> R {
+    model::with(|m| m.tb0.rd(f))
+}
+
+fn tb0_ctl_wr(f: impl FnOnce(u16) -> u16) {
+    model::with(|m| {
+        let value = f(m.tb0.ctl);
+        m.tb0.ctl_wr(value);
+        m.log("TB0CTL", value);
+    });
+}
+
+impl TimerB for TB0 {
+    fn reset(&self) {
+        tb0_ctl_wr(|_| TBCLR);
+    }
+
+    fn upmode(&self) {
+        tb0_ctl_wr(|r| (r & !(TBIFG | MC)) | TBCLR | (1 << 4));
+    }
+
+    fn continuous(&self) {
+        tb0_ctl_wr(|r| (r & !(TBIFG | MC)) | TBCLR | (2 << 4));
+    }
+
+    fn updown(&self) {
+        tb0_ctl_wr(|r| (r & !(TBIFG | MC)) | TBCLR | (3 << 4));
+    }
+
+    fn config_clock(
+        &self,
+        tbssel: Tbssel,
+        div: TimerDiv,
+        cntl: CounterLength,
+        clgrp: CompareGroup,
+    ) {
+        tb0_ctl_wr(|_| {
+            ((tbssel as u16) << 8)
+                | ((div as u16) << 6)
+                | ((cntl as u16) << 11)
+                | ((clgrp as u16) << 13)
+        });
+    }
+
+    fn is_stopped(&self) -> bool {
+        tb0_rd(|t| t.mc() == 0)
+    }
+
+    fn stop(&self) {
+        tb0_ctl_wr(|r| r & !MC);
+    }
+
+    fn set_tbidex(&self, tbidex: TimerExDiv) {
+        model::with(|m| {
+            m.tb0.ex0 = tbidex as u16;
+            m.log("TB0EX0", tbidex as u16);
+        });
+    }
+
+    fn set_id_reset(&self, div: TimerDiv) {
+        tb0_ctl_wr(|r| (r & !ID) | ((div as u16) << 6) | TBCLR);
+    }
+
+    fn tbxr_rd(&self) -> u16 {
+        tb0_rd(|t| t.r)
+    }
+
+    fn tbifg_rd(&self) -> bool {
+        tb0_rd(|t| t.ctl & TBIFG != 0)
+    }
+
+    fn tbifg_clr(&self) {
+        tb0_ctl_wr(|r| r & !TBIFG);
+    }
+
+    fn tbie_set(&self) {
+        tb0_ctl_wr(|r| r | TBIE);
+    }
+
+    fn tbie_clr(&self) {
+        tb0_ctl_wr(|r| r & !TBIE);
+    }
+
+    fn tbxiv_rd(&self) -> u16 {
+        tb0_rd(|t| t.take_iv())
+    }
+}
+
+fn tb0_cctl_wr(n: usize, reg: &'static str, f: impl FnOnce(u16) -> u16) {
+    model::with(|m| {
+        let value = f(m.tb0.cctl_rd(n));
+        m.tb0.cctl_wr(n, value);
+        m.log(reg, value);
+    });
+}
+
+macro_rules! ccrn_impl {
+    ($CCRn:ident, $n:literal, $cctl:literal, $ccr:literal) => {
+        impl CCRn<$CCRn> for TB0 {
+            fn set_ccrn(&self, count: u16) {
+                model::with(|m| {
+                    m.tb0.ccr_wr($n, count);
+                    m.log($ccr, count);
+                });
+            }
+
+            fn get_ccrn(&self) -> u16 {
+                tb0_rd(|t| t.ccr[$n])
+            }
+
+            fn outmod_rd(&self) -> Outmod {
+                ((tb0_rd(|t| t.cctl[$n]) >> 5) as u8).into()
+            }
+
+            fn config_outmod(&self, outmod: Outmod) {
+                tb0_cctl_wr($n, $cctl, |r| (r & CLLD) | ((outmod as u16) << 5));
+            }
+
+            fn config_out(&self, out: bool) {
+                tb0_cctl_wr($n, $cctl, |r| {
+                    (r & CLLD) | ((Outmod::Out as u16) << 5) | if out { OUT } else { 0 }
+                });
+            }
+
+            fn config_clld(&self, clld: CompareLoad) {
+                tb0_cctl_wr($n, $cctl, |r| (r & !CLLD) | ((clld as u16) << 9));
+            }
+
+            fn config_cap_mode(&self, cm: Cm, ccis: Ccis) {
+                tb0_cctl_wr($n, $cctl, |_| {
+                    CAP | SCS | ((cm as u16) << 14) | ((ccis as u16) << 12)
+                });
+            }
+
+            fn ccifg_rd(&self) -> bool {
+                tb0_rd(|t| t.cctl[$n] & CCIFG != 0)
+            }
+
+            fn ccifg_clr(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r & !CCIFG);
+            }
+
+            fn ccifg_set(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r | CCIFG);
+            }
+
+            fn cci_rd(&self) -> bool {
+                tb0_rd(|t| t.cci($n))
+            }
+
+            fn cap_sel_rd(&self) -> (Cm, Ccis) {
+                let cctl = tb0_rd(|t| t.cctl[$n]);
+                (((cctl >> 14) as u8).into(), ((cctl >> 12) as u8).into())
+            }
+
+            fn config_cap_sel(&self, cm: Cm, ccis: Ccis) {
+                tb0_cctl_wr($n, $cctl, |r| {
+                    (r & !(CM | CCIS)) | ((cm as u16) << 14) | ((ccis as u16) << 12)
+                });
+            }
+
+            fn ccie_rd(&self) -> bool {
+                tb0_rd(|t| t.cctl[$n] & CCIE != 0)
+            }
+
+            fn ccie_set(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r | CCIE);
+            }
+
+            fn ccie_clr(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r & !CCIE);
+            }
+
+            fn cov_ccifg_rd(&self) -> (bool, bool) {
+                let cctl = tb0_rd(|t| t.cctl[$n]);
+                (cctl & COV != 0, cctl & CCIFG != 0)
+            }
+
+            fn cov_ccifg_clr(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r & !(COV | CCIFG));
+            }
+
+            fn cov_set(&self) {
+                tb0_cctl_wr($n, $cctl, |r| r | COV);
+            }
+        }
+    };
+}
+
+ccrn_impl!(CCR0, 0, "TB0CCTL0", "TB0CCR0");
+ccrn_impl!(CCR1, 1, "TB0CCTL1", "TB0CCR1");
+ccrn_impl!(CCR2, 2, "TB0CCTL2", "TB0CCR2");
+ccrn_impl!(CCR3, 3, "TB0CCTL3", "TB0CCR3");
+ccrn_impl!(CCR4, 4, "TB0CCTL4", "TB0CCR4");
+ccrn_impl!(CCR5, 5, "TB0CCTL5", "TB0CCR5");
+ccrn_impl!(CCR6, 6, "TB0CCTL6", "TB0CCR6");
+
+/// Value of UCAxSTATW
+pub struct Statw(u16);
+
+impl UcaxStatw for Statw {
+    fn ucfe(&self) -> bool {
+        self.0 & UCFE != 0
+    }
+
+    fn ucoe(&self) -> bool {
+        self.0 & UCOE != 0
+    }
+
+    fn ucpe(&self) -> bool {
+        self.0 & UCPE != 0
+    }
+
+    fn ucbrk(&self) -> bool {
+        self.0 & UCBRK != 0
+    }
+
+    fn ucbusy(&self) -> bool {
+        self.0 & UCBUSY != 0
+    }
+}
+
+macro_rules! eusci_a_impl {
+    ($EUsci:ident, $n:literal, $ctlw0:literal, $brw:literal, $mctlw:literal, $statw:literal,
+     $txbuf:literal, $ie:literal) => {
+        impl $EUsci {
+            fn ie_wr(&self, f: impl FnOnce(u16) -> u16) {
+                model::with(|m| {
+                    let value = f(m.usci[$n].ie);
+                    m.usci[$n].ie = value;
+                    m.log($ie, value);
+                });
+            }
+        }
+
+        impl EUsci for $EUsci {
+            fn ctl0_reset(&self) {
+                model::with(|m| {
+                    m.usci[$n].ctlw0_wr(UCSWRST);
+                    m.log($ctlw0, UCSWRST);
+                });
+            }
+
+            fn brw_settings(&self, ucbr: u16) {
+                model::with(|m| {
+                    m.usci[$n].brw = ucbr;
+                    m.log($brw, ucbr);
+                });
+            }
+
+            fn loopback(&self, loopback: bool) {
+                let value = if loopback { UCLISTEN } else { 0 };
+                model::with(|m| {
+                    m.usci[$n].statw = value;
+                    m.log($statw, value);
+                });
+            }
+
+            fn rx_rd(&self) -> u8 {
+                model::with(|m| m.usci[$n].rxbuf_rd())
+            }
+
+            fn tx_wr(&self, val: u8) {
+                model::with(|m| {
+                    m.log($txbuf, val as u16);
+                    m.usci[$n].txbuf_wr(val);
+                });
+            }
+
+            fn txie_set(&self) {
+                self.ie_wr(|r| r | UCTXIFG);
+            }
+
+            fn txie_clear(&self) {
+                self.ie_wr(|r| r & !UCTXIFG);
+            }
+
+            fn rxie_set(&self) {
+                self.ie_wr(|r| r | UCRXIFG);
+            }
+
+            fn rxie_clear(&self) {
+                self.ie_wr(|r| r & !UCRXIFG);
+            }
+
+            fn txifg_rd(&self) -> bool {
+                model::with(|m| m.usci[$n].ifg & UCTXIFG != 0)
+            }
+
+            fn rxifg_rd(&self) -> bool {
+                model::with(|m| m.usci[$n].ifg & UCRXIFG != 0)
+            }
+
+            fn iv_rd(&self) -> u16 {
+                model::with(|m| m.usci[$n].take_iv())
+            }
+        }
+
+        impl EUsciUart for $EUsci {
+            type Statw = Statw;
+
+            fn ctl0_settings(&self, reg: UcxCtl0) {
+                let value = ((reg.ucpen as u16) << 15)
+                    | ((reg.ucpar as u16) << 14)
+                    | ((reg.ucmsb as u16) << 13)
+                    | ((reg.uc7bit as u16) << 12)
+                    | ((reg.ucspb as u16) << 11)
+                    | ((reg.ucssel as u16) << 6)
+                    | ((reg.ucrxeie as u16) << 5);
+                model::with(|m| {
+                    m.usci[$n].ctlw0_wr(value);
+                    m.log($ctlw0, value);
+                });
+            }
+
+            fn mctlw_settings(&self, ucos16: bool, ucbrs: u8, ucbrf: u8) {
+                let value = ((ucbrs as u16) << 8) | ((ucbrf as u16) << 4) | ucos16 as u16;
+                model::with(|m| {
+                    m.usci[$n].mctlw = value;
+                    m.log($mctlw, value);
+                });
+            }
+
+            fn statw_rd(&self) -> Statw {
+                Statw(model::with(|m| m.usci[$n].statw))
+            }
+        }
+    };
+}
+
+eusci_a_impl!(
+    E_USCI_A0,
+    0,
+    "UCA0CTLW0",
+    "UCA0BRW",
+    "UCA0MCTLW",
+    "UCA0STATW",
+    "UCA0TXBUF",
+    "UCA0IE"
+);
+eusci_a_impl!(
+    E_USCI_A1,
+    1,
+    "UCA1CTLW0",
+    "UCA1BRW",
+    "UCA1MCTLW",
+    "UCA1STATW",
+    "UCA1TXBUF",
+    "UCA1IE"
+);
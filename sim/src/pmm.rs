@@ -0,0 +1,17 @@
+//! Power management module
+
+use crate::{model, PMM};
+
+/// PMM type
+pub struct Pmm(());
+
+impl Pmm {
+    /// Clears the LOCKLPM5 bit and returns a `Pmm`
+    pub fn new(_pmm: PMM) -> Pmm {
+        model::with(|m| {
+            m.locklpm5 = false;
+            m.log("PM5CTL0", 0);
+        });
+        Pmm(())
+    }
+}
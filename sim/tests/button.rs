@@ -0,0 +1,163 @@
+use msp430fr247x_hal_sim::{
+    button::*,
+    gpio::{Batch, Pin0, Pullup, P1},
+    model,
+    pmm::Pmm,
+    Peripherals,
+};
+
+fn button(config: ButtonConfig) -> Button<P1, Pin0, Pullup> {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let parts = Batch::new(periph.P1)
+        .config_pin0(|p| p.pullup())
+        .split(&pmm);
+    release();
+    Button::new(parts.pin0, config)
+}
+
+fn press() {
+    model::with(|m| m.ports[0].input &= !0x01);
+}
+
+fn release() {
+    model::with(|m| m.ports[0].input |= 0x01);
+}
+
+// Tick `n` times and collect the events
+fn ticks(button: &mut Button<P1, Pin0, Pullup>, n: u32) -> Vec<ButtonEvent> {
+    (0..n).filter_map(|_| button.tick()).collect()
+}
+
+#[test]
+fn press_and_release_are_debounced() {
+    let mut button = button(ButtonConfig::new().debounce(3).double_click(0));
+
+    press();
+    assert_eq!(ticks(&mut button, 2), []);
+    assert!(!button.is_pressed());
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+    assert!(button.is_pressed());
+
+    // A short bounce doesn't release the button
+    release();
+    assert_eq!(ticks(&mut button, 1), []);
+    press();
+    assert_eq!(ticks(&mut button, 5), []);
+
+    release();
+    assert_eq!(ticks(&mut button, 3), [ButtonEvent::Release]);
+    assert!(!button.is_pressed());
+    assert_eq!(ticks(&mut button, 1), []);
+    assert!(button.is_idle());
+}
+
+#[test]
+fn long_press_is_emitted_once() {
+    let mut button = button(
+        ButtonConfig::new()
+            .debounce(1)
+            .long_press(10)
+            .double_click(0),
+    );
+
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+    assert_eq!(ticks(&mut button, 9), []);
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::LongPress]);
+    assert_eq!(ticks(&mut button, 100), []);
+
+    release();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Release]);
+}
+
+#[test]
+fn longest_long_press_is_emitted_once() {
+    let mut button = button(
+        ButtonConfig::new()
+            .debounce(1)
+            .long_press(u16::MAX)
+            .double_click(0),
+    );
+
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+    assert_eq!(ticks(&mut button, u16::MAX as u32 - 1), []);
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::LongPress]);
+    // `elapsed` saturates here
+    assert_eq!(ticks(&mut button, 1000), []);
+}
+
+#[test]
+fn disabled_long_press_is_never_emitted() {
+    let mut button = button(
+        ButtonConfig::new()
+            .debounce(1)
+            .long_press(0)
+            .double_click(0),
+    );
+
+    press();
+    assert_eq!(ticks(&mut button, 70_000), [ButtonEvent::Press]);
+}
+
+#[test]
+fn second_press_within_the_window_is_a_double_click() {
+    let mut button = button(ButtonConfig::new().debounce(1).double_click(5));
+
+    press();
+    assert_eq!(ticks(&mut button, 2), [ButtonEvent::Press]);
+    release();
+    assert_eq!(ticks(&mut button, 2), [ButtonEvent::Release]);
+    assert!(!button.is_idle());
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::DoubleClick]);
+    release();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Release]);
+
+    // The press after a double click starts a new sequence
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+}
+
+#[test]
+fn second_press_after_the_window_is_a_press() {
+    let mut button = button(ButtonConfig::new().debounce(1).double_click(5));
+
+    press();
+    assert_eq!(ticks(&mut button, 2), [ButtonEvent::Press]);
+    release();
+    assert_eq!(ticks(&mut button, 6), [ButtonEvent::Release]);
+    assert!(button.is_idle());
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+}
+
+#[test]
+fn long_press_does_not_start_a_double_click() {
+    let mut button = button(
+        ButtonConfig::new()
+            .debounce(1)
+            .long_press(3)
+            .double_click(5),
+    );
+
+    press();
+    assert_eq!(
+        ticks(&mut button, 4),
+        [ButtonEvent::Press, ButtonEvent::LongPress]
+    );
+    release();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Release]);
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+}
+
+#[test]
+fn active_high_button_presses_on_high_level() {
+    let mut button = button(ButtonConfig::new().active_high().debounce(1));
+
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Press]);
+    press();
+    assert_eq!(ticks(&mut button, 1), [ButtonEvent::Release]);
+}
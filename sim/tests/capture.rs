@@ -0,0 +1,319 @@
+use msp430fr247x_hal_sim::{
+    capture::{
+        measure::{Measure, PulseError},
+        *,
+    },
+    clock::{Aclk, Clock, Refo, Smclk, Vlo},
+    gpio::Batch,
+    model::{self, CciInput, SquareWave},
+    pmm::Pmm,
+    timer::CounterLength,
+    Peripherals, TB0,
+};
+
+fn smclk_config() -> TimerConfig<TB0> {
+    TimerConfig::smclk(&Smclk::new(8_000_000))
+}
+
+#[test]
+fn commit_configures_every_channel() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p4 = Batch::new(periph.P4)
+        .config_pin7(|p| p.to_alternate2())
+        .split(&pmm);
+    model::take_writes();
+
+    let _parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap1_input_A(p4.pin7)
+        .config_cap1_trigger(CapTrigger::FallingEdge)
+        .config_cap2_input_B()
+        .config_cap2_trigger(CapTrigger::BothEdges)
+        .commit();
+    // Unconfigured channels capture rising edges of GND, and the timer starts in continuous mode
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("TB0CTL", 0x0004),
+            ("TB0EX0", 0),
+            ("TB0CTL", 0x0200),
+            ("TB0CCTL0", 0x6900),
+            ("TB0CCTL1", 0x8900),
+            ("TB0CCTL2", 0xD900),
+            ("TB0CCTL3", 0x6900),
+            ("TB0CCTL4", 0x6900),
+            ("TB0CCTL5", 0x6900),
+            ("TB0CCTL6", 0x6900),
+            ("TB0CTL", 0x0224),
+        ]
+    );
+}
+
+#[test]
+fn captures_and_overcaptures_edges() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap3_input_B()
+        .commit();
+
+    assert!(parts.cap3.capture().is_err());
+    let rise = model::with(|m| {
+        m.tb0.advance(40);
+        m.tb0.set_cci(3, CciInput::B, true);
+        m.tb0.r
+    });
+    assert_eq!(parts.cap3.capture().ok(), Some(rise));
+    assert!(parts.cap3.capture().is_err());
+
+    // Falling edges aren't captured, and a second capture before the read sets COV
+    let rise = model::with(|m| {
+        m.tb0.advance(10);
+        m.tb0.set_cci(3, CciInput::B, false);
+        m.tb0.advance(10);
+        m.tb0.set_cci(3, CciInput::B, true);
+        m.tb0.advance(10);
+        m.tb0.set_cci(3, CciInput::B, false);
+        m.tb0.advance(10);
+        m.tb0.set_cci(3, CciInput::B, true);
+        m.tb0.r
+    });
+    match parts.cap3.capture() {
+        Err(nb::Error::Other(OverCapture(count))) => assert_eq!(count, rise),
+        _ => panic!("expected an overcapture"),
+    }
+    assert_eq!(
+        model::with(|m| m.tb0.cctl[3] & (model::COV | model::CCIFG)),
+        0
+    );
+}
+
+#[test]
+fn interrupt_vector_hands_out_the_capture() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap3_input_B()
+        .commit();
+    parts.cap3.enable_interrupts();
+    model::take_writes();
+
+    let rise = model::with(|m| {
+        m.tb0.advance(25);
+        m.tb0.set_cci(3, CciInput::B, true);
+        m.tb0.r
+    });
+    match parts.tbxiv.interrupt_vector() {
+        CaptureVector::Capture3(token) => {
+            assert_eq!(token.interrupt_capture(&mut parts.cap3).ok(), Some(rise))
+        }
+        _ => panic!("expected a capture 3 interrupt"),
+    }
+    assert!(matches!(
+        parts.tbxiv.interrupt_vector(),
+        CaptureVector::NoInterrupt
+    ));
+}
+
+#[test]
+fn software_capture_restores_the_configuration() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap2_input_B()
+        .config_cap2_trigger(CapTrigger::FallingEdge)
+        .commit();
+    model::with(|m| m.tb0.advance(300));
+    model::take_writes();
+
+    let count = parts.cap2.software_capture();
+    assert!((300..310).contains(&count));
+    // Captures are disabled before switching the input, so only the GND to VCC edge is captured
+    let cctl = model::take_writes_to("TB0CCTL2");
+    assert_eq!(cctl[..2], [("TB0CCTL2", 0x9900), ("TB0CCTL2", 0x1900)]);
+    assert_eq!(cctl.last(), Some(&("TB0CCTL2", 0x9900)));
+    assert!(cctl.contains(&("TB0CCTL2", 0xF900)));
+    assert_eq!(model::with(|m| m.tb0.cctl[2]), 0x9900);
+}
+
+#[test]
+fn software_capture_keeps_a_pending_capture_and_its_interrupt() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap3_input_B()
+        .commit();
+    parts.cap3.enable_interrupts();
+    let rise = model::with(|m| {
+        m.tb0.advance(50);
+        m.tb0.set_cci(3, CciInput::B, true);
+        m.tb0.r
+    });
+    model::take_writes();
+
+    let count = parts.cap3.software_capture();
+    assert!(count > rise);
+    // The interrupt is masked before the input is switched, and only enabled again once the
+    // pending capture is back in place
+    let cctl = model::take_writes_to("TB0CCTL3");
+    assert_eq!(cctl.first(), Some(&("TB0CCTL3", 0x5909)));
+    assert_eq!(cctl.last(), Some(&("TB0CCTL3", 0x5919)));
+    assert!(cctl[1..cctl.len() - 1]
+        .iter()
+        .all(|&(_, value)| value & model::CCIE == 0));
+    assert_eq!(model::with(|m| m.tb0.ccr[3]), rise);
+    match parts.tbxiv.interrupt_vector() {
+        CaptureVector::Capture3(token) => {
+            assert_eq!(token.interrupt_capture(&mut parts.cap3).ok(), Some(rise))
+        }
+        _ => panic!("expected a capture 3 interrupt"),
+    }
+}
+
+#[test]
+fn measures_a_square_wave() {
+    let periph = Peripherals::take().unwrap();
+    let parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap4_input_B()
+        .config_cap4_trigger(CapTrigger::BothEdges)
+        .commit();
+    // 250 ticks high out of 1000 at 8 MHz, so 8 kHz with a 25% duty cycle
+    model::with(|m| {
+        m.tb0.set_cci_wave(
+            4,
+            CciInput::B,
+            SquareWave {
+                period: 1000,
+                high: 250,
+            },
+        )
+    });
+
+    let mut measure = Measure::new(parts.cap4);
+    assert_eq!(measure.period_ticks().ok(), Some(1000));
+    assert_eq!(measure.frequency_avg(4).ok(), Some(8000));
+    assert_eq!(measure.pulse_width_ticks().ok(), Some(250));
+    let pulse = measure.pulse_avg(3).ok().unwrap();
+    assert_eq!((pulse.period, pulse.high), (1000, 250));
+    assert_eq!(measure.duty_permille().ok(), Some(250));
+}
+
+#[test]
+fn measures_periods_longer_than_the_counter() {
+    let periph = Peripherals::take().unwrap();
+    let parts = CaptureParts7::config(
+        periph.TB0,
+        smclk_config().counter_length(CounterLength::_8Bit),
+    )
+    .config_cap5_input_B()
+    .commit();
+    model::with(|m| {
+        m.tb0.set_cci_wave(
+            5,
+            CciInput::B,
+            SquareWave {
+                period: 700,
+                high: 350,
+            },
+        )
+    });
+
+    let mut measure = Measure::new(parts.cap5);
+    assert_eq!(measure.period_ticks_avg(2).ok(), Some(700));
+}
+
+#[test]
+fn pulse_measurements_require_both_edges() {
+    let periph = Peripherals::take().unwrap();
+    let parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap4_input_B()
+        .config_cap4_trigger(CapTrigger::RisingEdge)
+        .commit();
+    model::with(|m| {
+        m.tb0.set_cci_wave(
+            4,
+            CciInput::B,
+            SquareWave {
+                period: 1000,
+                high: 250,
+            },
+        )
+    });
+
+    let mut measure = Measure::new(parts.cap4);
+    assert!(matches!(
+        measure.pulse_width_ticks(),
+        Err(PulseError::NotBothEdges)
+    ));
+    assert!(matches!(measure.pulse(), Err(PulseError::NotBothEdges)));
+    assert_eq!(measure.period_ticks().ok(), Some(1000));
+}
+
+#[test]
+fn measurements_give_up_on_signals_faster_than_the_level_sampling() {
+    let periph = Peripherals::take().unwrap();
+    let parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap4_input_B()
+        .config_cap4_trigger(CapTrigger::BothEdges)
+        .commit();
+    // An edge on every timer tick
+    model::with(|m| {
+        m.tb0
+            .set_cci_wave(4, CciInput::B, SquareWave { period: 2, high: 1 })
+    });
+
+    let mut measure = Measure::new(parts.cap4);
+    assert!(measure.period_ticks().is_err());
+    assert!(matches!(
+        measure.pulse_width_ticks(),
+        Err(PulseError::OverCapture(_))
+    ));
+}
+
+#[test]
+fn calibrates_vloclk_against_smclk() {
+    let periph = Peripherals::take().unwrap();
+    let mut aclk = Aclk::<Vlo>::new();
+    let parts = CaptureParts7::config(periph.TB0, smclk_config())
+        .config_cap0_input_aclk(&aclk)
+        .commit();
+    // ACLK on CCI0B, with VLOCLK running at about 9.5 kHz instead of its nominal 10 kHz
+    model::with(|m| {
+        m.tb0.set_cci_wave(
+            0,
+            CciInput::B,
+            SquareWave {
+                period: 842,
+                high: 421,
+            },
+        )
+    });
+
+    let mut measure = Measure::new(parts.cap0);
+    assert_eq!(aclk.calibrate(&mut measure, 4).ok(), Some(9501));
+    assert_eq!(aclk.freq(), 9501);
+}
+
+#[test]
+fn calibrates_smclk_against_refoclk() {
+    let periph = Peripherals::take().unwrap();
+    let aclk = Aclk::<Refo>::new();
+    let mut smclk = Smclk::new(8_000_000);
+    let parts = CaptureParts7::config(periph.TB0, TimerConfig::smclk(&smclk))
+        .config_cap0_input_aclk(&aclk)
+        .commit();
+    // 244 SMCLK periods per REFOCLK period, so SMCLK is slightly slow
+    model::with(|m| {
+        m.tb0.set_cci_wave(
+            0,
+            CciInput::B,
+            SquareWave {
+                period: 244,
+                high: 122,
+            },
+        )
+    });
+
+    let mut measure = Measure::new(parts.cap0);
+    assert_eq!(
+        smclk.calibrate(&mut measure, &aclk, 8).ok(),
+        Some(244 * 32768)
+    );
+    assert_eq!(smclk.freq(), 244 * 32768);
+}
@@ -0,0 +1,92 @@
+use embedded_hal::digital::v2::*;
+use msp430fr247x_hal_sim::{gpio::*, model, pmm::Pmm, Peripherals};
+
+#[test]
+fn batch_commits_each_register_once() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    model::take_writes();
+
+    Batch::new(periph.P1)
+        .config_pin0(|p| p.to_output())
+        .config_pin2(|p| p.select_falling_edge_trigger())
+        .config_pin3(|p| p.pullup())
+        .config_pin4(|p| p.to_alternate1())
+        .split(&pmm);
+
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("P1SEL0", 0x10),
+            ("P1OUT", 0x08),
+            ("P1DIR", 0x01),
+            ("P1REN", 0x08),
+            ("P1IES", 0x04),
+            ("P1IFG", 0x00),
+        ]
+    );
+}
+
+#[test]
+fn recommit_only_writes_changes_and_keeps_output_levels() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+
+    let mut parts = Batch::new(periph.P2)
+        .config_pin0(|p| p.to_output())
+        .config_pin3(|p| p.pullup())
+        .split(&pmm);
+    parts.pin0.set_high().ok();
+    model::take_writes();
+
+    let parts = parts.batch().config_pin1(|p| p.to_output()).split(&pmm);
+    assert_eq!(model::take_writes(), [("P2DIR", 0x03)]);
+    assert!(parts.pin0.is_set_high().unwrap());
+    assert!(parts.pin1.is_set_low().unwrap());
+
+    parts
+        .batch()
+        .config_pin0(|p| p.to_input_floating())
+        .split(&pmm);
+    // Floating inputs keep their PxOUT bit
+    assert_eq!(model::take_writes(), [("P2DIR", 0x02)]);
+}
+
+#[test]
+fn pins_read_the_applied_levels() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let mut parts = Batch::new(periph.P3)
+        .config_pin5(|p| p.to_output())
+        .split(&pmm);
+
+    model::with(|m| m.ports[2].input = 0b0010_0100);
+    assert!(parts.pin2.is_high().unwrap());
+    assert!(parts.pin3.is_low().unwrap());
+    assert!(parts.pin5.is_set_low().unwrap());
+
+    model::take_writes();
+    parts.pin5.toggle().ok();
+    parts.pin5.toggle().ok();
+    assert_eq!(model::take_writes(), [("P3OUT", 0x20), ("P3OUT", 0x00)]);
+}
+
+#[test]
+fn interrupt_vector_reports_and_clears_the_pending_pin() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let mut parts = Batch::new(periph.P4)
+        .config_pin1(|p| p.select_rising_edge_trigger())
+        .config_pin6(|p| p.select_falling_edge_trigger())
+        .split(&pmm);
+    parts.pin1.enable_interrupts();
+    parts.pin6.enable_interrupts();
+    assert_eq!(model::with(|m| m.ports[3].ie), 0b0100_0010);
+
+    model::with(|m| m.ports[3].ifg = 0b0100_0010);
+    assert_eq!(parts.pxiv.get_interrupt_vector().pin_num(), Some(1));
+    let (vector, edge) = parts.pxiv.get_interrupt_vector_edge();
+    assert_eq!((vector.pin_num(), edge), (Some(6), Edge::Falling));
+    assert_eq!(parts.pxiv.get_interrupt_vector().pin_num(), None);
+    assert_eq!(model::with(|m| m.ports[3].ifg), 0);
+}
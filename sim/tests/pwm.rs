@@ -0,0 +1,253 @@
+use embedded_hal::PwmPin;
+use msp430fr247x_hal_sim::{clock::Smclk, gpio::Batch, model, pmm::Pmm, pwm::*, Peripherals, TB0};
+
+fn smclk_config() -> TimerConfig<TB0> {
+    TimerConfig::smclk(&Smclk::new(1_000_000))
+}
+
+#[test]
+fn edge_aligned_setup_and_duty() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p4 = Batch::new(periph.P4)
+        .config_pin7(|p| p.to_output().to_alternate2())
+        .split(&pmm);
+    model::take_writes();
+
+    let parts = PwmParts7::new(periph.TB0, smclk_config(), 1000);
+    // CCR0 toggles and holds the period, CCR1 to CCR6 are in reset/set mode, then up mode starts
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("TB0CTL", 0x0004),
+            ("TB0EX0", 0),
+            ("TB0CTL", 0x0200),
+            ("TB0CCR0", 1000),
+            ("TB0CCTL0", 0x0080),
+            ("TB0CCTL1", 0x00E0),
+            ("TB0CCTL2", 0x00E0),
+            ("TB0CCTL3", 0x00E0),
+            ("TB0CCTL4", 0x00E0),
+            ("TB0CCTL5", 0x00E0),
+            ("TB0CCTL6", 0x00E0),
+            ("TB0CTL", 0x0214),
+        ]
+    );
+
+    let mut pwm1 = parts.pwm1.init(p4.pin7);
+    pwm1.set_duty(250);
+    assert_eq!(model::take_writes(), [("TB0CCR1", 250)]);
+    assert_eq!(pwm1.get_duty(), 250);
+    assert_eq!(pwm1.get_max_duty(), 1000);
+
+    // P4.7 switches between its GPIO and TB0.1 functions through PxSEL1
+    pwm1.disable();
+    pwm1.enable();
+    assert_eq!(model::take_writes(), [("P4SEL1", 0x00), ("P4SEL1", 0x80)]);
+}
+
+#[test]
+fn center_aligned_drives_extreme_duties_as_constant_levels() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p5 = Batch::new(periph.P5)
+        .config_pin0(|p| p.to_output().to_alternate2())
+        .split(&pmm);
+
+    let parts = PwmParts7::new_center_aligned(periph.TB0, smclk_config(), 500);
+    assert_eq!(model::take_writes().last(), Some(&("TB0CTL", 0x0234)));
+
+    let mut pwm2 = parts.pwm2.init(p5.pin0);
+    pwm2.set_duty(0);
+    pwm2.set_duty(500);
+    pwm2.set_duty(100);
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("TB0CCR2", 0),
+            ("TB0CCTL2", 0x0000),
+            ("TB0CCR2", 500),
+            ("TB0CCTL2", 0x0004),
+            ("TB0CCR2", 100),
+            ("TB0CCTL2", 0x0040),
+        ]
+    );
+}
+
+#[test]
+fn group_update_loads_all_duties_at_the_end_of_the_period() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(
+        periph.TB0,
+        smclk_config().compare_group(CompareGroup::Triples),
+        100,
+    );
+    parts.group.set_compare_load(CompareLoad::AtZero);
+    let writes = model::take_writes();
+    assert_eq!(writes.first(), Some(&("TB0CTL", 0x0004)));
+    assert_eq!(writes[2], ("TB0CTL", 0x4200));
+    assert!(writes[writes.len() - 7..]
+        .iter()
+        .all(|(reg, value)| reg.starts_with("TB0CCTL") && value & model::CLLD == 0x0200));
+
+    parts.group.update_group(|duties| {
+        for (n, duty) in duties.iter_mut().enumerate() {
+            *duty = 10 * (n as u16 + 1);
+        }
+    });
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("TB0CCR1", 10),
+            ("TB0CCR2", 20),
+            ("TB0CCR3", 30),
+            ("TB0CCR4", 40),
+            ("TB0CCR5", 50),
+            ("TB0CCR6", 60),
+        ]
+    );
+    // The duties take effect together once the counter wraps to 0
+    assert_eq!(model::with(|m| m.tb0.cl), [100, 0, 0, 0, 0, 0, 0]);
+    model::with(|m| m.tb0.advance(101));
+    assert_eq!(model::with(|m| m.tb0.cl), [100, 10, 20, 30, 40, 50, 60]);
+}
+
+#[test]
+fn group_update_rewrites_the_period_when_all_latches_are_grouped() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(
+        periph.TB0,
+        smclk_config().compare_group(CompareGroup::All),
+        100,
+    );
+    parts.group.set_compare_load(CompareLoad::AtZero);
+    model::take_writes();
+
+    parts.group.update_group(|duties| duties[2] = 70);
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("TB0CCR0", 100),
+            ("TB0CCR1", 0),
+            ("TB0CCR2", 0),
+            ("TB0CCR3", 70),
+            ("TB0CCR4", 0),
+            ("TB0CCR5", 0),
+            ("TB0CCR6", 0),
+        ]
+    );
+    assert_eq!(model::with(|m| m.tb0.cl[3]), 0);
+    model::with(|m| m.tb0.advance(101));
+    assert_eq!(model::with(|m| m.tb0.cl), [100, 0, 0, 70, 0, 0, 0]);
+}
+
+#[test]
+fn group_update_does_not_wait_for_a_stopped_timer() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(
+        periph.TB0,
+        smclk_config().compare_group(CompareGroup::Pairs),
+        100,
+    );
+    parts.group.set_compare_load(CompareLoad::AtZero);
+    model::with(|m| m.tb0.ctl &= !model::MC);
+    model::take_writes();
+
+    parts.group.update_group(|duties| duties[0] = 30);
+    assert_eq!(model::take_writes().len(), 6);
+    // Nothing loads until the timer runs again
+    assert_eq!(model::with(|m| m.tb0.cl[1]), 0);
+}
+
+#[test]
+fn clock_divider_change_stops_and_restarts_the_timer() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(periph.TB0, smclk_config(), 1000);
+    model::with(|m| m.tb0.advance(300));
+    model::take_writes();
+
+    parts.timer.set_clk_div(TimerDiv::_2, TimerExDiv::_1);
+    // Stop, TBIDEX, ID = /2 with TBCLR, then the halved period and up mode again
+    let writes = model::take_writes();
+    assert_eq!(
+        writes[..3],
+        [("TB0CTL", 0x0200), ("TB0EX0", 0), ("TB0CTL", 0x0244)]
+    );
+    assert_eq!(
+        writes[writes.len() - 2..],
+        [("TB0CCR0", 499), ("TB0CTL", 0x0254)]
+    );
+    assert_eq!(parts.timer.tick_freq(), 500_000);
+    assert!(model::with(|m| m.tb0.r) < 5);
+}
+
+#[test]
+fn shorter_period_is_written_after_the_duties() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(periph.TB0, smclk_config(), 999);
+    embedded_hal::Pwm::set_duty(&mut parts.timer, PwmChannel::Pwm1, 600);
+    model::take_writes();
+
+    parts.timer.set_period_ticks(500);
+    let writes = model::take_writes();
+    assert_eq!(writes.first(), Some(&("TB0CCR1", 299)));
+    assert_eq!(writes.last(), Some(&("TB0CCR0", 499)));
+}
+
+#[test]
+fn longer_period_is_written_before_the_duties() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = PwmParts7::new(periph.TB0, smclk_config(), 499);
+    embedded_hal::Pwm::set_duty(&mut parts.timer, PwmChannel::Pwm1, 300);
+    model::take_writes();
+
+    parts.timer.set_period_ticks(1000);
+    let writes = model::take_writes();
+    assert_eq!(writes[..2], [("TB0CCR0", 999), ("TB0CCR1", 600)]);
+}
+
+#[test]
+fn period_change_keeps_the_dead_time_of_complementary_pairs() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p4 = Batch::new(periph.P4)
+        .config_pin7(|p| p.to_output().to_alternate2())
+        .split(&pmm);
+    let p5 = Batch::new(periph.P5)
+        .config_pin0(|p| p.to_output().to_alternate2())
+        .split(&pmm);
+
+    let mut parts = PwmParts7::new_center_aligned(periph.TB0, smclk_config(), 500);
+    let mut pair = parts
+        .pwm1
+        .init_complementary(parts.pwm2, p4.pin7, p5.pin0, 10);
+    pair.set_duty(200);
+    model::take_writes();
+
+    // Doubling the period doubles the duty cycle but not the dead time
+    parts.timer.set_period_ticks(2000);
+    assert_eq!(
+        model::take_writes_to("TB0CCR")[..3],
+        [("TB0CCR0", 1000), ("TB0CCR1", 400), ("TB0CCR2", 410)]
+    );
+    assert_eq!(pair.get_duty(), 400);
+    assert_eq!(pair.dead_time(), 10);
+
+    // A low side held low is still recognized as part of the pair
+    pair.set_duty(995);
+    assert_eq!(model::with(|m| m.tb0.cctl[2] & model::OUTMOD), 5 << 5);
+    parts.timer.set_period_ticks(1000);
+    assert_eq!(
+        model::take_writes_to("TB0CCR")[2..],
+        [
+            ("TB0CCR1", 497),
+            ("TB0CCR2", 507),
+            ("TB0CCR3", 0),
+            ("TB0CCR4", 0),
+            ("TB0CCR5", 0),
+            ("TB0CCR6", 0),
+            ("TB0CCR0", 500),
+        ]
+    );
+    assert_eq!(model::with(|m| m.tb0.cctl[2] & model::OUTMOD), 5 << 5);
+}
@@ -0,0 +1,151 @@
+use embedded_hal::serial::{Read, Write};
+use msp430fr247x_hal_sim::{
+    clock::{Aclk, Refo, Smclk},
+    gpio::Batch,
+    model,
+    pmm::Pmm,
+    serial::*,
+    Peripherals, E_USCI_A0,
+};
+use nb::block;
+
+#[test]
+fn config_writes_the_user_guide_sequence() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p1 = Batch::new(periph.P1)
+        .config_pin4(|p| p.to_alternate1())
+        .config_pin5(|p| p.to_alternate1())
+        .split(&pmm);
+    model::take_writes();
+
+    SerialConfig::new(
+        periph.E_USCI_A0,
+        BitOrder::LsbFirst,
+        BitCount::EightBits,
+        StopBits::OneStopBit,
+        Parity::EvenParity,
+        Loopback::NoLoop,
+        115200,
+    )
+    .use_smclk(&Smclk::new(8_000_000))
+    .split(p1.pin4, p1.pin5);
+
+    // UCSWRST is held while configuring, and UCOS16 = 1, UCBRx = 4, UCBRFx = 5, UCBRSx = 0x55
+    // match the 8 MHz, 115200 baud row of the user's guide
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("UCA0CTLW0", 0x0001),
+            ("UCA0BRW", 4),
+            ("UCA0MCTLW", 0x5551),
+            ("UCA0STATW", 0x0000),
+            ("UCA0CTLW0", 0xC0A0),
+        ]
+    );
+}
+
+#[test]
+fn aclk_config_selects_aclk_and_low_frequency_baud_generation() {
+    let periph = Peripherals::take().unwrap();
+    let pmm = Pmm::new(periph.PMM);
+    let p2 = Batch::new(periph.P2)
+        .config_pin6(|p| p.to_alternate1())
+        .split(&pmm);
+    model::take_writes();
+
+    SerialConfig::new(
+        periph.E_USCI_A1,
+        BitOrder::MsbFirst,
+        BitCount::SevenBits,
+        StopBits::TwoStopBits,
+        Parity::NoParity,
+        Loopback::NoLoop,
+        9600,
+    )
+    .use_aclk(&Aclk::<Refo>::new())
+    .tx_only(p2.pin6);
+
+    assert_eq!(
+        model::take_writes(),
+        [
+            ("UCA1CTLW0", 0x0001),
+            ("UCA1BRW", 3),
+            ("UCA1MCTLW", 0x9200),
+            ("UCA1STATW", 0x0000),
+            ("UCA1CTLW0", 0x3860),
+        ]
+    );
+}
+
+fn loopback_serial(periph: Peripherals) -> (Tx<E_USCI_A0>, Rx<E_USCI_A0>) {
+    let pmm = Pmm::new(periph.PMM);
+    let p1 = Batch::new(periph.P1)
+        .config_pin4(|p| p.to_alternate1())
+        .config_pin5(|p| p.to_alternate1())
+        .split(&pmm);
+    SerialConfig::new(
+        periph.E_USCI_A0,
+        BitOrder::LsbFirst,
+        BitCount::EightBits,
+        StopBits::OneStopBit,
+        Parity::NoParity,
+        Loopback::Loopback,
+        9600,
+    )
+    .use_smclk(&Smclk::new(1_000_000))
+    .split(p1.pin4, p1.pin5)
+}
+
+#[test]
+fn loopback_receives_the_sent_bytes() {
+    let (mut tx, mut rx) = loopback_serial(Peripherals::take().unwrap());
+
+    assert!(matches!(rx.read(), Err(nb::Error::WouldBlock)));
+    for byte in b"hi" {
+        block!(tx.write(*byte)).unwrap();
+        assert_eq!(block!(rx.read()).unwrap(), *byte);
+    }
+    block!(tx.flush()).unwrap();
+    assert_eq!(model::with(|m| m.usci[0].sent.clone()), b"hi");
+}
+
+#[test]
+fn receive_errors_are_reported_and_cleared() {
+    let (_tx, mut rx) = loopback_serial(Peripherals::take().unwrap());
+
+    model::with(|m| {
+        m.usci[0].receive(1);
+        m.usci[0].receive(2);
+    });
+    assert!(matches!(
+        rx.read(),
+        Err(nb::Error::Other(RecvError::Overrun(2)))
+    ));
+
+    model::with(|m| {
+        m.usci[0].receive(3);
+        m.usci[0].statw |= model::UCFE;
+    });
+    assert!(matches!(
+        rx.read(),
+        Err(nb::Error::Other(RecvError::Framing))
+    ));
+
+    model::with(|m| m.usci[0].receive(4));
+    assert_eq!(rx.read().unwrap(), 4);
+}
+
+#[test]
+fn interrupt_enables_only_touch_their_bit() {
+    let (mut tx, mut rx) = loopback_serial(Peripherals::take().unwrap());
+    model::take_writes();
+
+    rx.enable_rx_interrupts();
+    tx.enable_tx_interrupts();
+    rx.disable_rx_interrupts();
+    assert_eq!(
+        model::take_writes(),
+        [("UCA0IE", 0x01), ("UCA0IE", 0x03), ("UCA0IE", 0x02)]
+    );
+}
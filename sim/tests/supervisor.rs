@@ -0,0 +1,151 @@
+use msp430fr247x_hal_sim::deadlines::*;
+
+fn service_n<const N: usize>(deadlines: &mut Deadlines<N>, n: u16) -> Health {
+    let mut health = Health::Ok;
+    for _ in 0..n {
+        health = deadlines.service();
+    }
+    health
+}
+
+#[test]
+fn heartbeats_within_the_deadline_stay_healthy() {
+    let mut deadlines = Deadlines::<2>::new();
+    let task = deadlines.register_window(0, 4).unwrap();
+
+    for _ in 0..10 {
+        assert_eq!(service_n(&mut deadlines, 2), Health::Ok);
+        deadlines.heartbeat(task);
+    }
+}
+
+#[test]
+fn task_past_half_its_deadline_is_late() {
+    let mut deadlines = Deadlines::<2>::new();
+    let first = deadlines.register_window(0, 4).unwrap();
+    let second = deadlines.register_window(0, 4).unwrap();
+
+    assert_eq!(service_n(&mut deadlines, 2), Health::Ok);
+    assert_eq!(deadlines.service(), Health::Late(first));
+    deadlines.heartbeat(first);
+    assert_eq!(deadlines.service(), Health::Late(second));
+    deadlines.heartbeat(second);
+    assert_eq!(deadlines.service(), Health::Ok);
+}
+
+#[test]
+fn missed_deadline_starves_for_good() {
+    let mut deadlines = Deadlines::<2>::new();
+    deadlines.register_window(0, 8).unwrap();
+    let task = deadlines.register_window(0, 4).unwrap();
+
+    assert_eq!(service_n(&mut deadlines, 4), Health::Late(task));
+    let starvation = Starvation {
+        task,
+        cause: Cause::Missed,
+    };
+    assert_eq!(deadlines.service(), Health::Starved(starvation));
+
+    // A late heartbeat does not bring the task back
+    deadlines.heartbeat(task);
+    assert_eq!(deadlines.service(), Health::Starved(starvation));
+}
+
+#[test]
+fn first_heartbeat_is_not_checked_against_the_window() {
+    let mut deadlines = Deadlines::<1>::new();
+    let task = deadlines.register_window(3, 6).unwrap();
+
+    deadlines.heartbeat(task);
+    assert_eq!(deadlines.service(), Health::Ok);
+}
+
+#[test]
+fn heartbeats_within_the_window_stay_healthy() {
+    let mut deadlines = Deadlines::<1>::new();
+    let task = deadlines.register_window(3, 6).unwrap();
+
+    deadlines.heartbeat(task);
+    for _ in 0..5 {
+        assert_eq!(service_n(&mut deadlines, 3), Health::Ok);
+        deadlines.heartbeat(task);
+    }
+}
+
+#[test]
+fn heartbeat_before_the_window_starves() {
+    let mut deadlines = Deadlines::<1>::new();
+    let task = deadlines.register_window(3, 6).unwrap();
+
+    deadlines.heartbeat(task);
+    assert_eq!(service_n(&mut deadlines, 2), Health::Ok);
+    deadlines.heartbeat(task);
+    assert_eq!(
+        deadlines.service(),
+        Health::Starved(Starvation {
+            task,
+            cause: Cause::Early,
+        })
+    );
+}
+
+#[test]
+fn unregistered_task_is_not_checked() {
+    let mut deadlines = Deadlines::<1>::new();
+    let task = deadlines.register_window(0, 2).unwrap();
+    deadlines.unregister(task);
+
+    assert_eq!(service_n(&mut deadlines, 10), Health::Ok);
+    // The slot is free again
+    assert_eq!(deadlines.register_window(0, 2).unwrap(), task);
+}
+
+#[test]
+fn register_fails_when_all_slots_are_used() {
+    let mut deadlines = Deadlines::<2>::new();
+    assert_eq!(deadlines.register_window(0, 2).unwrap().index(), 0);
+    assert_eq!(deadlines.register_window(0, 2).unwrap().index(), 1);
+    assert!(deadlines.register_window(0, 2).is_err());
+}
+
+#[test]
+fn starvation_record_round_trips() {
+    let mut deadlines = Deadlines::<4>::new();
+    let task = (0..4)
+        .map(|_| deadlines.register_window(0, 100).unwrap())
+        .last()
+        .unwrap();
+    for cause in [Cause::Missed, Cause::Early] {
+        let starvation = Starvation { task, cause };
+        assert_eq!(
+            Starvation::from_record(starvation.to_record()),
+            Some(starvation)
+        );
+    }
+}
+
+#[test]
+fn starvation_record_layout() {
+    let mut deadlines = Deadlines::<4>::new();
+    let task = (0..4)
+        .map(|_| deadlines.register_window(0, 100).unwrap())
+        .last()
+        .unwrap();
+    let missed = Starvation {
+        task,
+        cause: Cause::Missed,
+    };
+    let early = Starvation {
+        task,
+        cause: Cause::Early,
+    };
+    assert_eq!(missed.to_record(), 0xA503);
+    assert_eq!(early.to_record(), 0xA583);
+}
+
+#[test]
+fn backup_memory_without_a_record_decodes_to_none() {
+    assert_eq!(Starvation::from_record(0), None);
+    assert_eq!(Starvation::from_record(0xFFFF), None);
+    assert_eq!(Starvation::from_record(0x5A03), None);
+}
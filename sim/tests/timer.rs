@@ -0,0 +1,134 @@
+use embedded_hal::timer::{Cancel, CountDown};
+use msp430fr247x_hal_sim::{
+    clock::{Aclk, Refo, Smclk},
+    model,
+    timer::*,
+    Peripherals, TB0,
+};
+
+fn timer_parts(config: impl FnOnce(TimerConfig<TB0>) -> TimerConfig<TB0>) -> TimerParts7<TB0> {
+    let periph = Peripherals::take().unwrap();
+    TimerParts7::new(
+        periph.TB0,
+        config(TimerConfig::smclk(&Smclk::new(1_000_000))),
+    )
+}
+
+#[test]
+fn config_writes_clock_settings() {
+    let parts = timer_parts(|c| {
+        c.clk_div(TimerDiv::_2, TimerExDiv::_3)
+            .counter_length(CounterLength::_12Bit)
+    });
+
+    // TBCLR, then TBIDEX = /3, then TBSSEL = SMCLK, ID = /2 and CNTL = 12 bits
+    assert_eq!(
+        model::take_writes(),
+        [("TB0CTL", 0x0004), ("TB0EX0", 2), ("TB0CTL", 0x0A40)]
+    );
+    assert_eq!(parts.timer.tick_freq(), 1_000_000 / 6);
+}
+
+#[test]
+fn countdown_flags_the_end_of_each_period() {
+    let mut parts = timer_parts(|c| c);
+    model::take_writes();
+
+    parts.timer.start(100u16);
+    assert_eq!(
+        model::take_writes(),
+        [("TB0CTL", 0x0200), ("TB0CCR0", 100), ("TB0CTL", 0x0214)]
+    );
+
+    model::with(|m| m.tb0.advance(100));
+    assert!(parts.timer.wait().is_err());
+    model::with(|m| m.tb0.advance(1));
+    assert!(parts.timer.wait().is_ok());
+    assert!(parts.timer.wait().is_err());
+
+    parts.timer.cancel().unwrap();
+    assert_eq!(model::with(|m| m.tb0.mc()), 0);
+}
+
+#[test]
+fn subtimers_fire_on_their_compare_value() {
+    let mut parts = timer_parts(|c| c);
+    parts.timer.start(1000u16);
+    parts.subtimer2.set_count(300);
+    parts.subtimer5.set_count(200);
+    parts.subtimer2.enable_interrupts();
+    parts.subtimer5.enable_interrupts();
+    model::take_writes();
+
+    model::with(|m| m.tb0.advance(250));
+    assert!(parts.subtimer2.wait().is_err());
+    model::with(|m| m.tb0.advance(50));
+    assert!(matches!(
+        parts.tbxiv.interrupt_vector(),
+        TimerVector::SubTimer2
+    ));
+    assert!(matches!(
+        parts.tbxiv.interrupt_vector(),
+        TimerVector::SubTimer5
+    ));
+    assert!(matches!(
+        parts.tbxiv.interrupt_vector(),
+        TimerVector::NoInterrupt
+    ));
+    // Reading the interrupt vector already cleared the flags
+    assert!(parts.subtimer5.wait().is_err());
+    assert_eq!(model::take_writes(), []);
+}
+
+#[test]
+fn count_reads_the_counter_once_on_smclk() {
+    let mut parts = timer_parts(|c| c);
+    parts.timer.start_continuous();
+
+    // The model counter advances on every read, so a single read returns the current value
+    model::with(|m| m.tb0.advance(200));
+    assert_eq!(parts.timer.count(), 200);
+    assert_eq!(parts.timer.count(), 201);
+}
+
+#[test]
+fn count_returns_on_an_asynchronous_clock_advancing_on_every_read() {
+    let periph = Peripherals::take().unwrap();
+    let mut parts = TimerParts7::new(periph.TB0, TimerConfig::aclk(&Aclk::<Refo>::new()));
+    parts.timer.start_continuous();
+
+    // No two reads agree, so the last of the three reads is taken
+    model::with(|m| m.tb0.advance(200));
+    assert_eq!(parts.timer.count(), 202);
+    assert_eq!(parts.timer.count(), 205);
+}
+
+#[test]
+fn extended_count_accounts_for_overflows() {
+    let mut parts = timer_parts(|c| c.counter_length(CounterLength::_8Bit));
+    parts.timer.start_continuous();
+
+    // Reading the counter takes a few ticks of its own
+    let mut overflows = 0;
+    model::with(|m| m.tb0.advance(200));
+    let before = parts.timer.extended_count(&mut overflows);
+    assert!((200..205).contains(&before));
+    model::with(|m| m.tb0.advance(100));
+    let after = parts.timer.extended_count(&mut overflows);
+    assert!((before + 100..before + 105).contains(&after));
+    assert_eq!(overflows, 1);
+}
+
+#[test]
+fn delay_ticks_chains_countdowns() {
+    let mut parts = timer_parts(|c| c.counter_length(CounterLength::_8Bit));
+    model::take_writes();
+
+    // 256 + 256 + 88 ticks, each countdown one tick longer than its CCR0 value
+    parts.timer.delay_ticks(600);
+    assert_eq!(
+        model::take_writes_to("TB0CCR0"),
+        [("TB0CCR0", 255), ("TB0CCR0", 255), ("TB0CCR0", 87)]
+    );
+    assert_eq!(model::with(|m| m.tb0.mc()), 0);
+}
@@ -0,0 +1,523 @@
+//! Async drivers
+//!
+//! Provides `Future`-based versions of GPIO edge waiting, timer delays, serial reads and writes,
+//! and captures, implementing the `embedded-hal-async` and `embedded-io-async` traits where
+//! applicable.
+//!
+//! Each future enables the interrupt of its peripheral while it is pending, and the interrupt
+//! handlers disable the interrupt again and wake the waiting task. The futures themselves check
+//! and clear the hardware flags, so the handlers never consume any events, and they only touch
+//! pins and channels that have a future waiting on them. Wakers are stored in statically
+//! allocated slots, so no allocator is required.
+//!
+//! The handlers are opt-in, since the vectors may be needed by other drivers, such as the
+//! monotonic timer or the timer wheel on `TIMER0_B1`. `async_interrupts!` defines the handlers
+//! for a list of vectors, which also clear the low-power mode bits on exit so an executor can
+//! sleep in between polls:
+//!
+//! ```ignore
+//! async_interrupts!(PORT1, TIMER0_B0, EUSCI_A0);
+//! ```
+//!
+//! A vector shared with another driver calls the `on_*_interrupt` function of the async drivers
+//! from its own handler instead, for example from an `irq_handler!` on `TIMER0_B1` calling
+//! `asynch::on_timer_interrupt::<TB0>()` next to the other driver.
+
+use crate::capture::{Capture, OverCapture};
+use crate::gpio::{Input, IntrPortNum, Pin, PinNum, P1, P2, P3, P4};
+use crate::hw_traits::eusci::{EUsciUart, UcaxStatw};
+use crate::hw_traits::gpio::IntrPeriph;
+use crate::hw_traits::timerb::{CCRn, TimerB};
+use crate::serial::{RecvError, Rx, SerialUsci, Tx};
+use crate::timer::{
+    CapCmp, CcrNum, Timer, TimerPeriph, CCR0, CCR1, CCR2, CCR3, CCR4, CCR5, CCR6,
+};
+use crate::util::BitsExt;
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+use critical_section::{with, Mutex};
+use msp430fr247x as pac;
+
+/// Storage for the waker of a single pending future
+#[doc(hidden)]
+pub struct WakerSlot(Mutex<RefCell<Option<Waker>>>);
+
+impl WakerSlot {
+    const fn new() -> Self {
+        WakerSlot(Mutex::new(RefCell::new(None)))
+    }
+
+    #[inline]
+    fn register(&self, waker: &Waker) {
+        with(|cs| {
+            let mut slot = self.0.borrow(cs).borrow_mut();
+            match slot.as_ref() {
+                Some(old) if old.will_wake(waker) => {}
+                _ => *slot = Some(waker.clone()),
+            }
+        });
+    }
+
+    // Wake the waiting task, returning whether there was one
+    #[inline]
+    fn wake(&self) -> bool {
+        match with(|cs| self.0.borrow(cs).borrow_mut().take()) {
+            Some(waker) => {
+                waker.wake();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Trait will not be used as a bound outside the HAL, so just keep it hidden
+#[doc(hidden)]
+pub trait PortWakers: IntrPeriph {
+    fn wakers() -> &'static [WakerSlot; 8];
+}
+
+// Trait will not be used as a bound outside the HAL, so just keep it hidden
+#[doc(hidden)]
+pub trait TimerWakers: TimerB {
+    fn wakers() -> &'static TimerWakerSlots;
+}
+
+// Trait will not be used as a bound outside the HAL, so just keep it hidden
+#[doc(hidden)]
+pub trait UsciWakers: EUsciUart {
+    fn wakers() -> &'static UsciWakerSlots;
+}
+
+#[doc(hidden)]
+pub struct TimerWakerSlots {
+    main: WakerSlot,
+    ccr: [WakerSlot; 7],
+}
+
+#[doc(hidden)]
+pub struct UsciWakerSlots {
+    tx: WakerSlot,
+    rx: WakerSlot,
+}
+
+macro_rules! port_wakers {
+    ($($Px:ident: $WAKERS:ident),*) => {
+        $(
+            static $WAKERS: [WakerSlot; 8] = [
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+                WakerSlot::new(),
+            ];
+
+            impl PortWakers for $Px {
+                #[inline(always)]
+                fn wakers() -> &'static [WakerSlot; 8] {
+                    &$WAKERS
+                }
+            }
+        )*
+    };
+}
+
+port_wakers!(P1: P1_WAKERS, P2: P2_WAKERS, P3: P3_WAKERS, P4: P4_WAKERS);
+
+static TB0_WAKERS: TimerWakerSlots = TimerWakerSlots {
+    main: WakerSlot::new(),
+    ccr: [
+        WakerSlot::new(),
+        WakerSlot::new(),
+        WakerSlot::new(),
+        WakerSlot::new(),
+        WakerSlot::new(),
+        WakerSlot::new(),
+        WakerSlot::new(),
+    ],
+};
+
+impl TimerWakers for pac::TB0 {
+    #[inline(always)]
+    fn wakers() -> &'static TimerWakerSlots {
+        &TB0_WAKERS
+    }
+}
+
+static USCI_A0_WAKERS: UsciWakerSlots = UsciWakerSlots {
+    tx: WakerSlot::new(),
+    rx: WakerSlot::new(),
+};
+
+static USCI_A1_WAKERS: UsciWakerSlots = UsciWakerSlots {
+    tx: WakerSlot::new(),
+    rx: WakerSlot::new(),
+};
+
+impl UsciWakers for pac::E_USCI_A0 {
+    #[inline(always)]
+    fn wakers() -> &'static UsciWakerSlots {
+        &USCI_A0_WAKERS
+    }
+}
+
+impl UsciWakers for pac::E_USCI_A1 {
+    #[inline(always)]
+    fn wakers() -> &'static UsciWakerSlots {
+        &USCI_A1_WAKERS
+    }
+}
+
+/// Interrupt handler of the async GPIO drivers of a port. Disables the interrupts of all pins with
+/// pending flags that have a future waiting on them, and wakes the futures. The flags are left set
+/// so the futures can observe them.
+#[inline(always)]
+pub fn on_port_interrupt<PORT: PortWakers>() {
+    let p = unsafe { PORT::steal() };
+    let pending = p.pxifg_rd() & p.pxie_rd();
+    for (num, slot) in PORT::wakers().iter().enumerate() {
+        if pending.check(num as u8) != 0 && slot.wake() {
+            p.pxie_clear(!(1 << num));
+        }
+    }
+}
+
+#[inline(always)]
+fn on_ccr_interrupt<T: TimerWakers + CCRn<C>, C: CcrNum>(timer: &T) {
+    if timer.ccifg_rd() && T::wakers().ccr[C::NUM].wake() {
+        timer.ccie_clr();
+    }
+}
+
+/// Interrupt handler of the async drivers for the `TIMERx_B0` vector of a timer, which only covers
+/// captures on CCR0
+#[inline(always)]
+pub fn on_timer_ccr0_interrupt<T: TimerWakers + CCRn<CCR0>>() {
+    let timer = unsafe { T::steal() };
+    on_ccr_interrupt::<_, CCR0>(&timer);
+}
+
+/// Interrupt handler of the async drivers for the `TIMERx_B1` vector of a timer, which covers
+/// delays and captures on CCR1 to CCR6. Doesn't read the interrupt vector register, so other
+/// drivers sharing the vector can still read it.
+#[inline(always)]
+pub fn on_timer_interrupt<T>()
+where
+    T: TimerWakers
+        + CCRn<CCR1>
+        + CCRn<CCR2>
+        + CCRn<CCR3>
+        + CCRn<CCR4>
+        + CCRn<CCR5>
+        + CCRn<CCR6>,
+{
+    let timer = unsafe { T::steal() };
+    on_ccr_interrupt::<_, CCR1>(&timer);
+    on_ccr_interrupt::<_, CCR2>(&timer);
+    on_ccr_interrupt::<_, CCR3>(&timer);
+    on_ccr_interrupt::<_, CCR4>(&timer);
+    on_ccr_interrupt::<_, CCR5>(&timer);
+    on_ccr_interrupt::<_, CCR6>(&timer);
+    if timer.tbifg_rd() && T::wakers().main.wake() {
+        timer.tbie_clr();
+    }
+}
+
+/// Interrupt handler of the async serial drivers of an eUSCI peripheral
+#[inline(always)]
+pub fn on_usci_interrupt<USCI: UsciWakers>() {
+    let usci = unsafe { USCI::steal() };
+    if usci.rxifg_rd() && USCI::wakers().rx.wake() {
+        usci.rxie_clear();
+    }
+    if usci.txifg_rd() && USCI::wakers().tx.wake() {
+        usci.txie_clear();
+    }
+}
+
+/// Define the interrupt handlers of the async drivers for a list of vectors, out of `PORT1` to
+/// `PORT4`, `TIMER0_B0`, `TIMER0_B1`, `EUSCI_A0` and `EUSCI_A1`. The handlers wake the CPU from
+/// low power mode. Requires the `abi_msp430_interrupt` and `asm_experimental_arch` features in the
+/// crate that uses it.
+#[macro_export]
+macro_rules! async_interrupts {
+    ($($vector:ident),* $(,)?) => {
+        const _: () = {
+            use $crate::pac::interrupt;
+            $($crate::async_interrupts!(@handler $vector);)*
+        };
+    };
+    (@handler PORT1) => { $crate::async_interrupts!(@port PORT1, P1); };
+    (@handler PORT2) => { $crate::async_interrupts!(@port PORT2, P2); };
+    (@handler PORT3) => { $crate::async_interrupts!(@port PORT3, P3); };
+    (@handler PORT4) => { $crate::async_interrupts!(@port PORT4, P4); };
+    (@handler TIMER0_B0) => {
+        #[interrupt(wake_cpu)]
+        fn TIMER0_B0() {
+            $crate::asynch::on_timer_ccr0_interrupt::<$crate::pac::TB0>();
+        }
+    };
+    (@handler TIMER0_B1) => {
+        #[interrupt(wake_cpu)]
+        fn TIMER0_B1() {
+            $crate::asynch::on_timer_interrupt::<$crate::pac::TB0>();
+        }
+    };
+    (@handler EUSCI_A0) => {
+        #[interrupt(wake_cpu)]
+        fn EUSCI_A0() {
+            $crate::asynch::on_usci_interrupt::<$crate::pac::E_USCI_A0>();
+        }
+    };
+    (@handler EUSCI_A1) => {
+        #[interrupt(wake_cpu)]
+        fn EUSCI_A1() {
+            $crate::asynch::on_usci_interrupt::<$crate::pac::E_USCI_A1>();
+        }
+    };
+    (@port $vector:ident, $Px:ident) => {
+        #[interrupt(wake_cpu)]
+        fn $vector() {
+            $crate::asynch::on_port_interrupt::<$crate::gpio::$Px>();
+        }
+    };
+}
+
+impl<PORT: IntrPortNum + PortWakers, PIN: PinNum, PULL> Pin<PORT, PIN, Input<PULL>> {
+    #[inline]
+    fn is_input_high(&self) -> bool {
+        let p = unsafe { PORT::steal() };
+        p.pxin_rd().check(PIN::NUM) != 0
+    }
+
+    // Changing PxIES can set the interrupt flag, so the flag is cleared afterwards. If `level` is
+    // set, the wait also ends once the input is at that level, which is checked after the edge is
+    // armed so an edge arriving in between isn't missed.
+    async fn wait_for_edge(&mut self, falling: bool, level: Option<bool>) {
+        let p = unsafe { PORT::steal() };
+        p.pxie_clear(PIN::CLR_MASK);
+        if falling {
+            p.pxies_set(PIN::SET_MASK);
+        } else {
+            p.pxies_clear(PIN::CLR_MASK);
+        }
+        p.pxifg_clear(PIN::CLR_MASK);
+
+        poll_fn(|cx| {
+            let p = unsafe { PORT::steal() };
+            if p.pxifg_rd().check(PIN::NUM) != 0 || level == Some(self.is_input_high()) {
+                p.pxie_clear(PIN::CLR_MASK);
+                p.pxifg_clear(PIN::CLR_MASK);
+                Poll::Ready(())
+            } else {
+                PORT::wakers()[PIN::NUM as usize].register(cx.waker());
+                p.pxie_set(PIN::SET_MASK);
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<PORT: IntrPortNum + PortWakers, PIN: PinNum, PULL> embedded_hal_async::digital::Wait
+    for Pin<PORT, PIN, Input<PULL>>
+{
+    #[inline]
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge(false, Some(true)).await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge(true, Some(false)).await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge(false, None).await;
+        Ok(())
+    }
+
+    #[inline]
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge(true, None).await;
+        Ok(())
+    }
+
+    /// The hardware only supports interrupts on one edge, so the edge opposite to the current
+    /// input level is awaited. An edge that arrives while the edge is being selected is caught by
+    /// the input level no longer matching.
+    #[inline]
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        let high = self.is_input_high();
+        self.wait_for_edge(high, Some(!high)).await;
+        Ok(())
+    }
+}
+
+impl<T: TimerPeriph + TimerWakers> Timer<T> {
+    async fn wait_async(&mut self) {
+        poll_fn(|cx| {
+            let timer = unsafe { T::steal() };
+            if timer.tbifg_rd() {
+                timer.tbie_clr();
+                timer.tbifg_clr();
+                Poll::Ready(())
+            } else {
+                T::wakers().main.register(cx.waker());
+                timer.tbie_set();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Wait asynchronously for the specified number of timer ticks. Tick counts that don't fit in
+    /// the timer period are handled by chaining multiple countdowns. The timer is stopped
+    /// afterwards. The shortest possible delay is 2 ticks.
+    pub async fn delay_ticks_async(&mut self, mut ticks: u32) {
+        use embedded_hal::timer::Cancel;
+
+        while ticks > 0 {
+            ticks = self.start_delay_chunk(ticks);
+            self.wait_async().await;
+        }
+        self.cancel().ok();
+    }
+}
+
+impl<T: TimerPeriph + TimerWakers> embedded_hal_async::delay::DelayNs for Timer<T> {
+    /// Delays are rounded up to the nearest timer tick, so the resolution of the delay depends
+    /// on the clock frequency of the timer.
+    #[inline]
+    async fn delay_ns(&mut self, ns: u32) {
+        let ticks = (ns as u64 * self.tick_freq() as u64).div_ceil(1_000_000_000);
+        self.delay_ticks_async(ticks as u32).await;
+    }
+}
+
+impl<T: CapCmp<C> + TimerWakers, C: CcrNum> Capture<T, C> {
+    /// Wait asynchronously for the next capture and return the captured timer value. Returns an
+    /// error if the previous capture was overwritten before being read.
+    pub async fn capture_async(&mut self) -> Result<u16, OverCapture> {
+        poll_fn(|cx| {
+            let timer = unsafe { T::steal() };
+            let (cov, ccifg) = timer.cov_ccifg_rd();
+            if ccifg {
+                timer.ccie_clr();
+                let ccrn = timer.get_ccrn();
+                timer.cov_ccifg_clr();
+                if cov {
+                    Poll::Ready(Err(OverCapture(ccrn)))
+                } else {
+                    Poll::Ready(Ok(ccrn))
+                }
+            } else {
+                T::wakers().ccr[C::NUM].register(cx.waker());
+                timer.ccie_set();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<USCI: SerialUsci + UsciWakers> Tx<USCI> {
+    async fn wait_tx_ready(&mut self) {
+        poll_fn(|cx| {
+            let usci = unsafe { USCI::steal() };
+            if usci.txifg_rd() {
+                Poll::Ready(())
+            } else {
+                USCI::wakers().tx.register(cx.waker());
+                usci.txie_set();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<USCI: SerialUsci + UsciWakers> embedded_io_async::Write for Tx<USCI> {
+    /// Waits until the first byte can be sent, then keeps writing bytes for as long as the Tx
+    /// buffer is ready without waiting.
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.wait_tx_ready().await;
+
+        let usci = unsafe { USCI::steal() };
+        let mut written = 0;
+        for &byte in buf {
+            if !usci.txifg_rd() {
+                break;
+            }
+            usci.tx_wr(byte);
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Waits until the Tx buffer is empty. The FIFO may still be sending afterwards.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.wait_tx_ready().await;
+        Ok(())
+    }
+}
+
+impl<USCI: SerialUsci + UsciWakers> embedded_io_async::Read for Rx<USCI> {
+    /// Waits until the first byte is received, then keeps reading bytes for as long as they are
+    /// available without waiting. An erroneous byte received after the first one is left in the Rx
+    /// buffer, so that its error is returned by the next read.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        poll_fn(|cx| {
+            let usci = unsafe { USCI::steal() };
+            if usci.rxifg_rd() {
+                Poll::Ready(())
+            } else {
+                USCI::wakers().rx.register(cx.waker());
+                usci.rxie_set();
+                Poll::Pending
+            }
+        })
+        .await;
+
+        let usci = unsafe { USCI::steal() };
+        let mut len = 0;
+        while len < buf.len() && usci.rxifg_rd() {
+            let statw = usci.statw_rd();
+            let error = if statw.ucfe() {
+                Some(RecvError::Framing)
+            } else if statw.ucpe() {
+                Some(RecvError::Parity)
+            } else if statw.ucoe() {
+                Some(RecvError::Overrun(0))
+            } else {
+                None
+            };
+            match error {
+                Some(_) if len > 0 => break,
+                Some(RecvError::Overrun(_)) => return Err(RecvError::Overrun(usci.rx_rd())),
+                Some(err) => {
+                    usci.rx_rd();
+                    return Err(err);
+                }
+                None => {
+                    buf[len] = usci.rx_rd();
+                    len += 1;
+                }
+            }
+        }
+        Ok(len)
+    }
+}
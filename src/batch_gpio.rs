@@ -8,6 +8,20 @@
 //! For example, `P2.batch().config_pin3(|p| p.to_input_pullup()).config_pin1(|p| p.to_output()).split(&pmm)`
 //! configures P2.3 as a pullup input pin and P2.1 as an output pin and then writes the
 //! configuration to the hardware in a single set of writes.
+//!
+//! Proxies can also be converted to alternate functions and have their interrupt edge selected,
+//! so a whole port can be configured in one commit. `Parts::batch` turns split pins back into a
+//! batch for reconfiguring the port at runtime, and committing only writes the registers that
+//! change. Pins that already were outputs keep their output level, while pins that become outputs
+//! start out low.
+//!
+//! To keep floating pins from drawing current in low power modes, `Batch::park_unconfigured` turns
+//! every pin that is still an unconfigured floating input into an `Unused` output driven low, and
+//! `Batch::precommit` writes the configuration before `Pmm::new` unlocks the I/Os.
+//! `Batch::assert_configured` turns leftover unconfigured pins into a compile error.
+//!
+//! The PAC exposes a PJ register block, but no PJ pins are bonded out on the MSP430FR247x
+//! packages, so there is no batch for port J.
 
 use crate::gpio::*;
 use crate::hw_traits::gpio::{GpioPeriph, IntrPeriph};
@@ -20,6 +34,8 @@ use core::marker::PhantomData;
 /// Configuring the proxy only changes the typestate of the proxy. Registers are only written once
 /// all the proxies for the GPIO port are "committed".
 pub struct PinProxy<PORT: PortNum, PIN: PinNum, DIR> {
+    // Interrupt edge to commit, `Some(true)` for falling edge. `None` keeps the current edge.
+    ies: Option<bool>,
     _port: PhantomData<PORT>,
     _pin: PhantomData<PIN>,
     _dir: PhantomData<DIR>,
@@ -27,7 +43,12 @@ pub struct PinProxy<PORT: PortNum, PIN: PinNum, DIR> {
 
 macro_rules! make_proxy {
     () => {
+        make_proxy!(None)
+    };
+
+    ($ies:expr) => {
         PinProxy {
+            ies: $ies,
             _port: PhantomData,
             _pin: PhantomData,
             _dir: PhantomData,
@@ -39,25 +60,25 @@ impl<PORT: PortNum, PIN: PinNum, PULL> PinProxy<PORT, PIN, Input<PULL>> {
     /// Configures pin as pulldown input
     #[inline(always)]
     pub fn pulldown(self) -> PinProxy<PORT, PIN, Input<Pulldown>> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 
     /// Configures pin as pullup input
     #[inline(always)]
     pub fn pullup(self) -> PinProxy<PORT, PIN, Input<Pullup>> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 
     /// Configures pin as floating input
     #[inline(always)]
     pub fn floating(self) -> PinProxy<PORT, PIN, Input<Floating>> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 
     /// Configures pin as output
     #[inline(always)]
     pub fn to_output(self) -> PinProxy<PORT, PIN, Output> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 }
 
@@ -65,25 +86,138 @@ impl<PORT: PortNum, PIN: PinNum> PinProxy<PORT, PIN, Output> {
     /// Configures pin as floating input
     #[inline(always)]
     pub fn to_input_floating(self) -> PinProxy<PORT, PIN, Input<Floating>> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 
     /// Configures pin as floating pullup
     #[inline(always)]
     pub fn to_input_pullup(self) -> PinProxy<PORT, PIN, Input<Pullup>> {
-        make_proxy!()
+        make_proxy!(self.ies)
     }
 
     /// Configures pin as floating pulldown
     #[inline(always)]
     pub fn to_input_pulldown(self) -> PinProxy<PORT, PIN, Input<Pulldown>> {
-        make_proxy!()
+        make_proxy!(self.ies)
+    }
+}
+
+impl<PORT: IntrPortNum, PIN: PinNum, PULL> PinProxy<PORT, PIN, Input<PULL>> {
+    /// Set interrupt trigger to rising edge. The interrupt flag is cleared on commit if the edge
+    /// changed.
+    #[inline(always)]
+    pub fn select_rising_edge_trigger(self) -> Self {
+        make_proxy!(Some(false))
+    }
+
+    /// Set interrupt trigger to falling edge. The interrupt flag is cleared on commit if the edge
+    /// changed.
+    #[inline(always)]
+    pub fn select_falling_edge_trigger(self) -> Self {
+        make_proxy!(Some(true))
+    }
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR: GpioFunction> PinProxy<PORT, PIN, DIR> {
+    /// Configures pin as alternate function 1
+    #[inline(always)]
+    pub fn to_alternate1(self) -> PinProxy<PORT, PIN, Alternate1<DIR>>
+    where
+        Pin<PORT, PIN, DIR>: ToAlternate1,
+    {
+        make_proxy!(self.ies)
+    }
+
+    /// Configures pin as alternate function 2
+    #[inline(always)]
+    pub fn to_alternate2(self) -> PinProxy<PORT, PIN, Alternate2<DIR>>
+    where
+        Pin<PORT, PIN, DIR>: ToAlternate2,
+    {
+        make_proxy!(self.ies)
+    }
+
+    /// Configures pin as alternate function 3
+    #[inline(always)]
+    pub fn to_alternate3(self) -> PinProxy<PORT, PIN, Alternate3<DIR>>
+    where
+        Pin<PORT, PIN, DIR>: ToAlternate3,
+    {
+        make_proxy!(self.ies)
+    }
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR> PinProxy<PORT, PIN, Alternate1<DIR>> {
+    /// Configures pin as GPIO
+    #[inline(always)]
+    pub fn to_gpio(self) -> PinProxy<PORT, PIN, DIR> {
+        make_proxy!(self.ies)
+    }
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR> PinProxy<PORT, PIN, Alternate2<DIR>> {
+    /// Configures pin as GPIO
+    #[inline(always)]
+    pub fn to_gpio(self) -> PinProxy<PORT, PIN, DIR> {
+        make_proxy!(self.ies)
+    }
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR> PinProxy<PORT, PIN, Alternate3<DIR>> {
+    /// Configures pin as GPIO
+    #[inline(always)]
+    pub fn to_gpio(self) -> PinProxy<PORT, PIN, DIR> {
+        make_proxy!(self.ies)
+    }
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR: GpioFunction> PinProxy<PORT, PIN, DIR> {
+    /// Marks pin as unused, driving it low as an output
+    #[inline(always)]
+    pub fn to_unused(self) -> PinProxy<PORT, PIN, Unused> {
+        make_proxy!(self.ies)
     }
 }
 
+/// Maps the typestate of an unconfigured pin, a floating input, to `Unused` and keeps all other
+/// typestates
+pub trait Park {
+    /// Typestate after parking
+    type Parked;
+    #[doc(hidden)]
+    const UNCONFIGURED: bool = false;
+}
+
+impl Park for Input<Floating> {
+    type Parked = Unused;
+    const UNCONFIGURED: bool = true;
+}
+impl Park for Input<Pullup> {
+    type Parked = Self;
+}
+impl Park for Input<Pulldown> {
+    type Parked = Self;
+}
+impl Park for Output {
+    type Parked = Self;
+}
+impl Park for Unused {
+    type Parked = Self;
+}
+impl<DIR> Park for Alternate1<DIR> {
+    type Parked = Self;
+}
+impl<DIR> Park for Alternate2<DIR> {
+    type Parked = Self;
+}
+impl<DIR> Park for Alternate3<DIR> {
+    type Parked = Self;
+}
+
 // Traits for deciding the value of a pin's registers
 trait PxdirOn {}
 trait PxoutOn {}
+trait PxoutKeep {}
 trait PxrenOn {}
 trait Pxsel0On {}
 trait Pxsel1On {}
@@ -120,6 +254,23 @@ impl<T: PxoutOn> WritePxout for T {
     }
 }
 
+// Pins whose typestate doesn't define their PxOUT bit, which is left to the pin's user
+trait KeepPxout {
+    fn pxout_keep(&self) -> bool;
+}
+impl<T> KeepPxout for T {
+    #[inline(always)]
+    default fn pxout_keep(&self) -> bool {
+        false
+    }
+}
+impl<T: PxoutKeep> KeepPxout for T {
+    #[inline(always)]
+    fn pxout_keep(&self) -> bool {
+        true
+    }
+}
+
 trait WritePxren {
     fn pxren_on(&self) -> bool;
 }
@@ -170,6 +321,7 @@ impl<T: Pxsel1On> WritePxsel1 for T {
 
 // Register marker trait implementations
 impl<PORT: PortNum, PIN: PinNum> PxdirOn for PinProxy<PORT, PIN, Output> {}
+impl<PORT: PortNum, PIN: PinNum> PxdirOn for PinProxy<PORT, PIN, Unused> {}
 impl<PORT: PortNum, PIN: PinNum> PxdirOn for PinProxy<PORT, PIN, Alternate1<Output>> {}
 impl<PORT: PortNum, PIN: PinNum> PxdirOn for PinProxy<PORT, PIN, Alternate2<Output>> {}
 impl<PORT: PortNum, PIN: PinNum> PxdirOn for PinProxy<PORT, PIN, Alternate3<Output>> {}
@@ -188,6 +340,15 @@ impl<PORT: PortNum, PIN: PinNum> PxoutOn for PinProxy<PORT, PIN, Alternate1<Inpu
 impl<PORT: PortNum, PIN: PinNum> PxoutOn for PinProxy<PORT, PIN, Alternate2<Input<Pullup>>> {}
 impl<PORT: PortNum, PIN: PinNum> PxoutOn for PinProxy<PORT, PIN, Alternate3<Input<Pullup>>> {}
 
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Output> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate1<Output>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate2<Output>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate3<Output>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Input<Floating>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate1<Input<Floating>>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate2<Input<Floating>>> {}
+impl<PORT: PortNum, PIN: PinNum> PxoutKeep for PinProxy<PORT, PIN, Alternate3<Input<Floating>>> {}
+
 impl<PORT: PortNum, PIN: PinNum, DIR> Pxsel0On for PinProxy<PORT, PIN, Alternate1<DIR>> {}
 impl<PORT: PortNum, PIN: PinNum, DIR> Pxsel0On for PinProxy<PORT, PIN, Alternate3<DIR>> {}
 
@@ -197,6 +358,7 @@ impl<PORT: PortNum, PIN: PinNum, DIR> Pxsel1On for PinProxy<PORT, PIN, Alternate
 // Derive bitmasks for different GPIO registers from pin numbers and register trait implementations
 trait MaskRegisters {
     fn pxout_mask(&self) -> u8;
+    fn pxout_keep_mask(&self) -> u8;
     fn pxdir_mask(&self) -> u8;
     fn pxren_mask(&self) -> u8;
     fn pxsel0_mask(&self) -> u8;
@@ -209,6 +371,11 @@ impl<PORT: PortNum, PIN: PinNum, DIR> MaskRegisters for PinProxy<PORT, PIN, DIR>
         (self.pxout_on() as u8) << PIN::NUM
     }
 
+    #[inline(always)]
+    fn pxout_keep_mask(&self) -> u8 {
+        (self.pxout_keep() as u8) << PIN::NUM
+    }
+
     #[inline(always)]
     fn pxdir_mask(&self) -> u8 {
         (self.pxdir_on() as u8) << PIN::NUM
@@ -231,18 +398,107 @@ impl<PORT: PortNum, PIN: PinNum, DIR> MaskRegisters for PinProxy<PORT, PIN, DIR>
 }
 
 trait InterruptOperations {
-    fn maybe_set_pxie(&self, b: u8);
+    fn maybe_clear_pxie(&self);
+    fn maybe_write_pxies(&self, mask: u8, bits: u8);
 }
 
 impl<P: GpioPeriph> InterruptOperations for P {
     #[inline(always)]
-    default fn maybe_set_pxie(&self, _b: u8) {}
+    default fn maybe_clear_pxie(&self) {}
+
+    #[inline(always)]
+    default fn maybe_write_pxies(&self, _mask: u8, _bits: u8) {}
 }
 
 impl<P: IntrPeriph> InterruptOperations for P {
     #[inline(always)]
-    fn maybe_set_pxie(&self, b: u8) {
-        self.pxie_set(b);
+    fn maybe_clear_pxie(&self) {
+        if self.pxie_rd() != 0 {
+            self.pxie_wr(0);
+        }
+    }
+
+    #[inline(always)]
+    fn maybe_write_pxies(&self, mask: u8, bits: u8) {
+        let old = self.pxies_rd();
+        let new = old.clear_mask(mask).set_mask(bits);
+        if new != old {
+            self.pxies_wr(new);
+            // Changing PxIES can set the interrupt flag, so clear the flags of the changed pins
+            self.pxifg_clear(!(old ^ new));
+        }
+    }
+}
+
+impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
+    Batch<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
+where
+    DIR0: Park,
+    DIR1: Park,
+    DIR2: Park,
+    DIR3: Park,
+    DIR4: Park,
+    DIR5: Park,
+    DIR6: Park,
+    DIR7: Park,
+{
+    /// Mask of the pins that are still unconfigured floating inputs
+    pub const UNCONFIGURED: u8 = (DIR0::UNCONFIGURED as u8)
+        | (DIR1::UNCONFIGURED as u8) << 1
+        | (DIR2::UNCONFIGURED as u8) << 2
+        | (DIR3::UNCONFIGURED as u8) << 3
+        | (DIR4::UNCONFIGURED as u8) << 4
+        | (DIR5::UNCONFIGURED as u8) << 5
+        | (DIR6::UNCONFIGURED as u8) << 6
+        | (DIR7::UNCONFIGURED as u8) << 7;
+
+    /// Marks all pins that are still floating inputs as unused, driving them low as outputs.
+    /// Floating pins draw current in low power modes, so this should be called once every pin
+    /// the application uses has been configured. Inputs meant to float can be converted back
+    /// after splitting.
+    #[inline(always)]
+    #[allow(clippy::type_complexity)]
+    pub fn park_unconfigured(
+        self,
+    ) -> Batch<
+        PORT,
+        DIR0::Parked,
+        DIR1::Parked,
+        DIR2::Parked,
+        DIR3::Parked,
+        DIR4::Parked,
+        DIR5::Parked,
+        DIR6::Parked,
+        DIR7::Parked,
+    > {
+        Batch {
+            pin0: make_proxy!(self.pin0.ies),
+            pin1: make_proxy!(self.pin1.ies),
+            pin2: make_proxy!(self.pin2.ies),
+            pin3: make_proxy!(self.pin3.ies),
+            pin4: make_proxy!(self.pin4.ies),
+            pin5: make_proxy!(self.pin5.ies),
+            pin6: make_proxy!(self.pin6.ies),
+            pin7: make_proxy!(self.pin7.ies),
+        }
+    }
+
+    /// Fails to compile if any pin is still an unconfigured floating input, naming the first such
+    /// pin
+    #[inline(always)]
+    pub fn assert_configured(self) -> Self {
+        const {
+            let unconfigured = Self::UNCONFIGURED;
+            assert!(unconfigured & 0x01 == 0, "pin 0 of the port is unconfigured");
+            assert!(unconfigured & 0x02 == 0, "pin 1 of the port is unconfigured");
+            assert!(unconfigured & 0x04 == 0, "pin 2 of the port is unconfigured");
+            assert!(unconfigured & 0x08 == 0, "pin 3 of the port is unconfigured");
+            assert!(unconfigured & 0x10 == 0, "pin 4 of the port is unconfigured");
+            assert!(unconfigured & 0x20 == 0, "pin 5 of the port is unconfigured");
+            assert!(unconfigured & 0x40 == 0, "pin 6 of the port is unconfigured");
+            assert!(unconfigured & 0x80 == 0, "pin 7 of the port is unconfigured");
+        }
+        self
     }
 }
 
@@ -293,6 +549,16 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
             .set_mask(self.pin6.pxdir_mask())
             .set_mask(self.pin7.pxdir_mask());
 
+        let pxout_keep = 0u8
+            .set_mask(self.pin0.pxout_keep_mask())
+            .set_mask(self.pin1.pxout_keep_mask())
+            .set_mask(self.pin2.pxout_keep_mask())
+            .set_mask(self.pin3.pxout_keep_mask())
+            .set_mask(self.pin4.pxout_keep_mask())
+            .set_mask(self.pin5.pxout_keep_mask())
+            .set_mask(self.pin6.pxout_keep_mask())
+            .set_mask(self.pin7.pxout_keep_mask());
+
         let pxout = 0u8
             .set_mask(self.pin0.pxout_mask())
             .set_mask(self.pin1.pxout_mask())
@@ -333,14 +599,55 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
             .set_mask(self.pin6.pxsel1_mask())
             .set_mask(self.pin7.pxsel1_mask());
 
+        // Only pins with an edge selected change their PxIES bit
+        let mut pxies_mask = 0u8;
+        let mut pxies = 0u8;
+        for (num, ies) in [
+            self.pin0.ies,
+            self.pin1.ies,
+            self.pin2.ies,
+            self.pin3.ies,
+            self.pin4.ies,
+            self.pin5.ies,
+            self.pin6.ies,
+            self.pin7.ies,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if let Some(falling) = ies {
+                pxies_mask = pxies_mask.set(num as u8);
+                if falling {
+                    pxies = pxies.set(num as u8);
+                }
+            }
+        }
+
         let p = unsafe { PORT::steal() };
+        // Pins that already were outputs keep their level and floating inputs keep whatever they
+        // had, but pins that become outputs start low
+        let pxout_keep = pxout_keep & (p.pxdir_rd() | !pxdir);
+        let pxout = pxout | (p.pxout_rd() & pxout_keep);
         // Turn off interrupts first so nothing fires during subsequent register writes
-        p.maybe_set_pxie(0);
-        p.pxsel0_wr(pxsel0);
-        p.pxsel1_wr(pxsel1);
-        p.pxout_wr(pxout);
-        p.pxdir_wr(pxdir);
-        p.pxren_wr(pxren);
+        p.maybe_clear_pxie();
+        // Registers that already hold the right value aren't written, so recommitting a batch of
+        // an already configured port only touches what changed
+        if p.pxsel0_rd() != pxsel0 {
+            p.pxsel0_wr(pxsel0);
+        }
+        if p.pxsel1_rd() != pxsel1 {
+            p.pxsel1_wr(pxsel1);
+        }
+        if p.pxout_rd() != pxout {
+            p.pxout_wr(pxout);
+        }
+        if p.pxdir_rd() != pxdir {
+            p.pxdir_wr(pxdir);
+        }
+        if p.pxren_rd() != pxren {
+            p.pxren_wr(pxren);
+        }
+        p.maybe_write_pxies(pxies_mask, pxies);
     }
 
     #[inline(always)]
@@ -358,7 +665,8 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
     }
 
     /// Commits all pin configurations to GPIO registers and returns GPIO parts and turns off all
-    /// interrupt enable bits.
+    /// interrupt enable bits. Only registers whose value changes are written, so a port can be
+    /// reconfigured at runtime by turning its `Parts` back into a `Batch` and splitting it again.
     ///
     /// Note that the pin's interrupt flags may become set as a result of
     /// this operation.
@@ -372,6 +680,16 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         Parts::new()
     }
 
+    /// Writes the pin configurations to the GPIO registers without splitting the batch. While
+    /// LOCKLPM5 is still set, which is before `Pmm::new` is called, the pins keep their state and
+    /// switch to the new configuration once the I/Os are unlocked, so unused pins never float.
+    /// Splitting the batch afterwards writes nothing, since the registers are already up to date.
+    #[inline]
+    pub fn precommit(self) -> Self {
+        self.write_regs();
+        self
+    }
+
     /// Edit configuration of pin 0
     #[inline(always)]
     pub fn config_pin0<NEW, F: FnOnce(PinProxy<PORT, Pin0, DIR0>) -> PinProxy<PORT, Pin0, NEW>>(
@@ -380,13 +698,13 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
     ) -> Batch<PORT, NEW, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
         Batch {
             pin0: f(self.pin0),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin1: self.pin1,
+            pin2: self.pin2,
+            pin3: self.pin3,
+            pin4: self.pin4,
+            pin5: self.pin5,
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -397,14 +715,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, NEW, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
         Batch {
-            pin0: make_proxy!(),
+            pin0: self.pin0,
             pin1: f(self.pin1),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin2: self.pin2,
+            pin3: self.pin3,
+            pin4: self.pin4,
+            pin5: self.pin5,
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -415,14 +733,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, NEW, DIR3, DIR4, DIR5, DIR6, DIR7> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
             pin2: f(self.pin2),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin3: self.pin3,
+            pin4: self.pin4,
+            pin5: self.pin5,
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -433,14 +751,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, DIR2, NEW, DIR4, DIR5, DIR6, DIR7> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
+            pin2: self.pin2,
             pin3: f(self.pin3),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin4: self.pin4,
+            pin5: self.pin5,
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -451,14 +769,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, DIR2, DIR3, NEW, DIR5, DIR6, DIR7> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
+            pin2: self.pin2,
+            pin3: self.pin3,
             pin4: f(self.pin4),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin5: self.pin5,
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -469,14 +787,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, NEW, DIR6, DIR7> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
+            pin2: self.pin2,
+            pin3: self.pin3,
+            pin4: self.pin4,
             pin5: f(self.pin5),
-            pin6: make_proxy!(),
-            pin7: make_proxy!(),
+            pin6: self.pin6,
+            pin7: self.pin7,
         }
     }
 
@@ -487,14 +805,14 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, NEW, DIR7> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
+            pin2: self.pin2,
+            pin3: self.pin3,
+            pin4: self.pin4,
+            pin5: self.pin5,
             pin6: f(self.pin6),
-            pin7: make_proxy!(),
+            pin7: self.pin7,
         }
     }
 
@@ -505,13 +823,13 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
         f: F,
     ) -> Batch<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, NEW> {
         Batch {
-            pin0: make_proxy!(),
-            pin1: make_proxy!(),
-            pin2: make_proxy!(),
-            pin3: make_proxy!(),
-            pin4: make_proxy!(),
-            pin5: make_proxy!(),
-            pin6: make_proxy!(),
+            pin0: self.pin0,
+            pin1: self.pin1,
+            pin2: self.pin2,
+            pin3: self.pin3,
+            pin4: self.pin4,
+            pin5: self.pin5,
+            pin6: self.pin6,
             pin7: f(self.pin7),
         }
     }
@@ -0,0 +1,240 @@
+//! Debounced buttons
+//!
+//! `Button` wraps an input pin and samples it on every tick of a periodic tick source, such as a
+//! `Timer`, an `Rtc` or a `Wdt` in interval mode. A pin level only counts once it has been stable
+//! for the configured number of ticks, and the debounced level is turned into press, release,
+//! long-press and double-click events. All durations are in ticks.
+//!
+//! In polled style, `Button::poll` waits on the tick source itself. In interrupt-driven style,
+//! `Button::tick` is called from the interrupt handler of the tick source, and the pin interrupt
+//! can be used to restart the tick source when the button is pressed while it was stopped, as
+//! reported by `Button::is_idle`. `Button::handles` checks whether a `PxIV` vector belongs to the
+//! button's pin.
+
+use crate::gpio::{GpioVector, Input, IntrPortNum, Pin, PinNum, PortNum};
+use embedded_hal::digital::v2::InputPin;
+use embedded_hal::timer::{CountDown, Periodic};
+use void::ResultVoidExt;
+
+/// Event produced by a debounced button
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ButtonEvent {
+    /// The button was pressed
+    Press,
+    /// The button was released
+    Release,
+    /// The button has been held down for the long-press duration. Emitted once per press.
+    LongPress,
+    /// The button was pressed a second time within the double-click window. Emitted instead of
+    /// the second `Press`.
+    DoubleClick,
+}
+
+/// Button timing and polarity configuration
+#[derive(Clone, Copy)]
+pub struct ButtonConfig {
+    active_low: bool,
+    debounce: u8,
+    long_press: u16,
+    double_click: u16,
+}
+
+impl ButtonConfig {
+    /// Active-low button stable after 3 ticks, with a long press after 100 ticks and a
+    /// double-click window of 25 ticks, which suits a 10 ms tick
+    #[inline]
+    pub const fn new() -> Self {
+        ButtonConfig {
+            active_low: true,
+            debounce: 3,
+            long_press: 100,
+            double_click: 25,
+        }
+    }
+
+    /// Button pulls the pin low when pressed, the default
+    #[inline]
+    pub const fn active_low(self) -> Self {
+        ButtonConfig {
+            active_low: true,
+            ..self
+        }
+    }
+
+    /// Button pulls the pin high when pressed
+    #[inline]
+    pub const fn active_high(self) -> Self {
+        ButtonConfig {
+            active_low: false,
+            ..self
+        }
+    }
+
+    /// Number of consecutive ticks a new level must be sampled before it's accepted
+    #[inline]
+    pub const fn debounce(self, ticks: u8) -> Self {
+        ButtonConfig {
+            debounce: if ticks == 0 { 1 } else { ticks },
+            ..self
+        }
+    }
+
+    /// Number of ticks the button must be held down for a long press. 0 disables long presses.
+    #[inline]
+    pub const fn long_press(self, ticks: u16) -> Self {
+        ButtonConfig {
+            long_press: ticks,
+            ..self
+        }
+    }
+
+    /// Maximum number of ticks between a release and the next press for a double click. 0
+    /// disables double clicks.
+    #[inline]
+    pub const fn double_click(self, ticks: u16) -> Self {
+        ButtonConfig {
+            double_click: ticks,
+            ..self
+        }
+    }
+}
+
+impl Default for ButtonConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Debounced button on an input pin
+pub struct Button<PORT: PortNum, PIN: PinNum, PULL> {
+    pin: Pin<PORT, PIN, Input<PULL>>,
+    config: ButtonConfig,
+    // Integrator moving between 0 (released) and `config.debounce` (pressed)
+    integrator: u8,
+    pressed: bool,
+    // Ticks since the last press while pressed, or since the last release while released
+    elapsed: u16,
+    // Whether the current press already produced a long press or double click, or its double click
+    // window has passed, in which case it can't start a double click
+    consumed: bool,
+    // Whether the current press already produced a long press. Tracked on its own since `elapsed`
+    // saturates and would otherwise match a long press of `u16::MAX` ticks on every tick.
+    long_pressed: bool,
+}
+
+impl<PORT: PortNum, PIN: PinNum, PULL> Button<PORT, PIN, PULL> {
+    /// Wrap an input pin. The button starts out released.
+    #[inline]
+    pub fn new(pin: Pin<PORT, PIN, Input<PULL>>, config: ButtonConfig) -> Self {
+        Button {
+            pin,
+            config,
+            integrator: 0,
+            pressed: false,
+            elapsed: u16::MAX,
+            consumed: true,
+            long_pressed: false,
+        }
+    }
+
+    /// Release the pin
+    #[inline]
+    pub fn free(self) -> Pin<PORT, PIN, Input<PULL>> {
+        self.pin
+    }
+
+    /// Debounced state of the button
+    #[inline]
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Whether the button is released and no event is pending, so the tick source can be
+    /// stopped until the next pin interrupt
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        !self.pressed && self.integrator == 0 && self.consumed
+    }
+
+    /// Sample the pin once. Must be called on every tick of the tick source.
+    pub fn tick(&mut self) -> Option<ButtonEvent> {
+        let active = self.pin.is_high().void_unwrap() != self.config.active_low;
+        if active {
+            self.integrator = self.integrator.saturating_add(1).min(self.config.debounce);
+        } else {
+            self.integrator = self.integrator.saturating_sub(1);
+        }
+        self.elapsed = self.elapsed.saturating_add(1);
+
+        if !self.pressed && self.integrator == self.config.debounce {
+            self.pressed = true;
+            let double = !self.consumed && self.elapsed <= self.config.double_click;
+            self.elapsed = 0;
+            self.consumed = double;
+            self.long_pressed = false;
+            return Some(if double {
+                ButtonEvent::DoubleClick
+            } else {
+                ButtonEvent::Press
+            });
+        }
+
+        if self.pressed && self.integrator == 0 {
+            self.pressed = false;
+            self.elapsed = 0;
+            return Some(ButtonEvent::Release);
+        }
+
+        if self.pressed
+            && !self.long_pressed
+            && self.config.long_press != 0
+            && self.elapsed >= self.config.long_press
+        {
+            self.long_pressed = true;
+            self.consumed = true;
+            return Some(ButtonEvent::LongPress);
+        }
+
+        // Close the double click window once it has passed, which also covers a window as long as
+        // the saturated `elapsed` count
+        if !self.pressed && self.elapsed >= self.config.double_click {
+            self.consumed = true;
+        }
+        None
+    }
+
+    /// Sample the pin if the tick source has ticked. Never blocks.
+    #[inline]
+    pub fn poll<C: CountDown + Periodic>(&mut self, ticker: &mut C) -> Option<ButtonEvent> {
+        match ticker.wait() {
+            Ok(()) => self.tick(),
+            Err(_) => None,
+        }
+    }
+
+    /// Whether an interrupt vector read from `PxIV` was caused by the button's pin
+    #[inline]
+    pub fn handles(&self, vector: &GpioVector) -> bool {
+        vector.pin_num() == Some(PIN::NUM)
+    }
+}
+
+impl<PORT: IntrPortNum, PIN: PinNum, PULL> Button<PORT, PIN, PULL> {
+    /// Enable the pin interrupt on the pressing edge, for restarting the tick source
+    #[inline]
+    pub fn enable_interrupts(&mut self) {
+        if self.config.active_low {
+            self.pin.select_falling_edge_trigger();
+        } else {
+            self.pin.select_rising_edge_trigger();
+        }
+        self.pin.enable_interrupts();
+    }
+
+    /// Disable the pin interrupt
+    #[inline]
+    pub fn disable_interrupts(&mut self) {
+        self.pin.disable_interrupt();
+    }
+}
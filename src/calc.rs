@@ -0,0 +1,609 @@
+//! Pure clock and timing calculations
+//!
+//! Register values derived from clock frequencies are computed here, away from the code that
+//! writes them to hardware: eUSCI baud rate settings, DCO FLL multipliers, FRAM wait states and
+//! watchdog intervals, along with the inverse calculations, such as the actual baud rate of a
+//! setting or the watchdog interval closest to a timeout in milliseconds.
+//!
+//! The module doesn't depend on the rest of the crate or on the PAC, so its tests can be run on
+//! the host with `rustc --edition 2021 --test src/calc.rs -o calc-tests && ./calc-tests`.
+
+/// REFOCLK frequency
+pub const REFOCLK: u16 = 32768;
+/// VLOCLK frequency
+pub const VLOCLK: u16 = 10000;
+/// Maximum MCLK frequency of the device
+pub const MCLK_MAX: u32 = 24_000_000;
+
+/// Selectable DCOCLK frequencies when using factory trim settings.
+/// Actual frequencies may be slightly higher.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DcoclkFreqSel {
+    /// 1 MHz
+    _1MHz,
+    /// 2 MHz
+    _2MHz,
+    /// 4 MHz
+    _4MHz,
+    /// 8 MHz
+    _8MHz,
+    /// 12 MHz
+    _12MHz,
+    /// 16 MHz
+    _16MHz,
+    /// 20 MHz
+    _20MHz,
+    /// 24 MHz
+    _24MHz,
+}
+
+impl DcoclkFreqSel {
+    /// All selectable frequencies, from lowest to highest
+    pub const ALL: [DcoclkFreqSel; 8] = [
+        DcoclkFreqSel::_1MHz,
+        DcoclkFreqSel::_2MHz,
+        DcoclkFreqSel::_4MHz,
+        DcoclkFreqSel::_8MHz,
+        DcoclkFreqSel::_12MHz,
+        DcoclkFreqSel::_16MHz,
+        DcoclkFreqSel::_20MHz,
+        DcoclkFreqSel::_24MHz,
+    ];
+
+    /// FLL multiplier of REFOCLK, which is one more than the value of FLLN
+    #[inline]
+    pub fn multiplier(self) -> u16 {
+        match self {
+            DcoclkFreqSel::_1MHz => 32,
+            DcoclkFreqSel::_2MHz => 61,
+            DcoclkFreqSel::_4MHz => 122,
+            DcoclkFreqSel::_8MHz => 245,
+            DcoclkFreqSel::_12MHz => 366,
+            DcoclkFreqSel::_16MHz => 490,
+            DcoclkFreqSel::_20MHz => 610,
+            DcoclkFreqSel::_24MHz => 732,
+        }
+    }
+
+    /// Numerical frequency
+    #[inline]
+    pub fn freq(self) -> u32 {
+        (self.multiplier() as u32) * (REFOCLK as u32)
+    }
+
+    /// Nominal frequency the setting is named after
+    #[inline]
+    pub fn nominal_freq(self) -> u32 {
+        match self {
+            DcoclkFreqSel::_1MHz => 1_000_000,
+            DcoclkFreqSel::_2MHz => 2_000_000,
+            DcoclkFreqSel::_4MHz => 4_000_000,
+            DcoclkFreqSel::_8MHz => 8_000_000,
+            DcoclkFreqSel::_12MHz => 12_000_000,
+            DcoclkFreqSel::_16MHz => 16_000_000,
+            DcoclkFreqSel::_20MHz => 20_000_000,
+            DcoclkFreqSel::_24MHz => 24_000_000,
+        }
+    }
+
+    /// Highest setting whose nominal frequency doesn't exceed `freq`, if any
+    #[inline]
+    pub fn at_most(freq: u32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .rev()
+            .find(|sel| sel.nominal_freq() <= freq)
+            .copied()
+    }
+}
+
+/// FRAM wait states
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaitStates {
+    /// No wait
+    Wait0,
+    /// Wait 1 cycle
+    Wait1,
+    /// Wait 2 cycles
+    Wait2,
+    /// Wait 3 cycles
+    Wait3,
+    /// Wait 4 cycles
+    Wait4,
+    /// Wait 5 cycles
+    Wait5,
+    /// Wait 6 cycles
+    Wait6,
+    /// Wait 7 cycles
+    Wait7,
+}
+
+impl WaitStates {
+    /// Fewest wait states needed for an MCLK frequency: 1 above 8 MHz and 2 above 16 MHz
+    #[inline]
+    pub fn for_mclk(mclk_freq: u32) -> Self {
+        if mclk_freq > 16_000_000 {
+            WaitStates::Wait2
+        } else if mclk_freq > 8_000_000 {
+            WaitStates::Wait1
+        } else {
+            WaitStates::Wait0
+        }
+    }
+
+    /// Highest MCLK frequency that can be used with the wait states
+    #[inline]
+    pub fn max_mclk(self) -> u32 {
+        match self {
+            WaitStates::Wait0 => 8_000_000,
+            WaitStates::Wait1 => 16_000_000,
+            _ => MCLK_MAX,
+        }
+    }
+}
+
+/// eUSCI UART baud rate settings
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BaudConfig {
+    /// Clock prescaler, UCBRx
+    pub br: u16,
+    /// Second modulation stage pattern, UCBRSx
+    pub brs: u8,
+    /// First modulation stage, UCBRFx. Only used with oversampling.
+    pub brf: u8,
+    /// Oversampling mode, UCOS16
+    pub ucos16: bool,
+}
+
+impl BaudConfig {
+    /// Average baud rate produced by the settings from a clock frequency
+    #[inline]
+    pub fn baudrate(&self, clk_freq: u32) -> u32 {
+        // Bit time in eighths of a clock period. Each set bit of the UCBRSx pattern stretches one
+        // of every 8 bits by a clock period.
+        let base = if self.ucos16 {
+            16 * self.br as u64 + self.brf as u64
+        } else {
+            self.br as u64
+        };
+        let eighths = (8 * base + self.brs.count_ones() as u64).max(1);
+        ((8 * clk_freq as u64 + eighths / 2) / eighths) as u32
+    }
+}
+
+/// Baud rate settings for a clock frequency and baud rate, as described in the user's guide.
+/// Oversampling is used whenever the clock is at least 16 times the baud rate.
+///
+/// UCBRSx comes from the guide's table of settings for the fractional part of the clock divider.
+/// The guide's table of typical settings instead searches for the UCBRSx with the lowest bit
+/// error over a character, so for some clock and baud rate pairs, such as 8 MHz at 9600 baud, it
+/// recommends a different UCBRSx than the one computed here.
+#[inline]
+pub fn calculate_baud_config(clk_freq: u32, bps: u32) -> BaudConfig {
+    // Prevent division by 0
+    let bps = bps.max(1);
+    // Ensure n stays within the 16 bit boundary
+    let n = (clk_freq / bps).clamp(1, 0xFFFF);
+
+    let brs = lookup_brs(clk_freq, bps);
+
+    if n >= 16 {
+        let div = bps * 16;
+        // n / 16, but more precise
+        let br = (clk_freq / div) as u16;
+        // same as n % 16, but more precise
+        let brf = ((clk_freq % div) / bps) as u8;
+        BaudConfig {
+            ucos16: true,
+            br,
+            brf,
+            brs,
+        }
+    } else {
+        BaudConfig {
+            ucos16: false,
+            br: n as u16,
+            brf: 0,
+            brs,
+        }
+    }
+}
+
+// UCBRSx settings for the fractional part of clk_freq / bps, in ten thousandths, from the table
+// in the user's guide, up to its last entry of 0.9288 for 0xFE. The setting of the highest fraction
+// not above the actual one, rounded to ten thousandths like the table, is used.
+const BRS_TABLE: [(u16, u8); 36] = [
+    (0, 0x00),
+    (529, 0x01),
+    (715, 0x02),
+    (835, 0x04),
+    (1001, 0x08),
+    (1252, 0x10),
+    (1430, 0x20),
+    (1670, 0x11),
+    (2147, 0x21),
+    (2224, 0x22),
+    (2503, 0x44),
+    (3000, 0x25),
+    (3335, 0x49),
+    (3575, 0x4A),
+    (3753, 0x52),
+    (4003, 0x92),
+    (4286, 0x53),
+    (4378, 0x55),
+    (5002, 0xAA),
+    (5715, 0x6B),
+    (6003, 0xAD),
+    (6254, 0xB5),
+    (6432, 0xB6),
+    (6667, 0xD6),
+    (7001, 0xB7),
+    (7147, 0xBB),
+    (7503, 0xDD),
+    (7861, 0xED),
+    (8004, 0xEE),
+    (8333, 0xBF),
+    (8464, 0xDF),
+    (8572, 0xEF),
+    (8751, 0xF7),
+    (9004, 0xFB),
+    (9170, 0xFD),
+    (9288, 0xFE),
+];
+
+#[inline]
+fn lookup_brs(clk_freq: u32, bps: u32) -> u8 {
+    let bps = bps as u64;
+    let frac = ((clk_freq as u64 % bps) * 10_000 + bps / 2) / bps;
+    BRS_TABLE
+        .iter()
+        .rev()
+        .find(|&&(f, _)| frac >= f as u64)
+        .map_or(0, |&(_, brs)| brs)
+}
+
+// Watchdog interval of each WDTIS setting, as a power of 2 of the clock period
+const WDT_SHIFTS: [u8; 8] = [31, 27, 23, 19, 15, 13, 9, 6];
+
+/// Error returned when a timeout can't be reached with any interval of the clock source
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OutOfRange;
+
+/// Watchdog interval selection
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WdtSetting {
+    /// Value of the WDTIS field
+    pub wdtis: u8,
+    /// Resulting interval in milliseconds, rounded to the nearest millisecond
+    pub timeout_ms: u32,
+}
+
+/// Number of clock periods of the watchdog interval selected by a WDTIS value
+#[inline]
+pub fn wdt_period_cycles(wdtis: u8) -> u32 {
+    1 << WDT_SHIFTS[(wdtis & 0x7) as usize]
+}
+
+/// Watchdog interval selected by a WDTIS value in milliseconds, rounded to the nearest
+/// millisecond
+#[inline]
+pub fn wdt_timeout_ms(clk_freq: u32, wdtis: u8) -> u32 {
+    let clk_freq = (clk_freq as u64).max(1);
+    ((wdt_period_cycles(wdtis) as u64 * 1000 + clk_freq / 2) / clk_freq) as u32
+}
+
+/// WDTIS value whose interval is closest to a timeout in milliseconds. Returns an error if the
+/// timeout is 0, or more than half a millisecond shorter than the shortest interval or longer than
+/// the longest interval of the clock.
+#[inline]
+pub fn wdt_setting(clk_freq: u32, timeout_ms: u32) -> Result<WdtSetting, OutOfRange> {
+    // Compare everything in thousandths of a clock period
+    let wanted = timeout_ms as u64 * clk_freq as u64;
+    let half_ms = clk_freq as u64 / 2;
+    let shortest = wdt_period_cycles(7) as u64 * 1000;
+    let longest = wdt_period_cycles(0) as u64 * 1000;
+    if timeout_ms == 0 || wanted + half_ms < shortest || wanted - half_ms > longest {
+        return Err(OutOfRange);
+    }
+
+    let wdtis = (0..8u8)
+        .min_by_key(|&wdtis| (wdt_period_cycles(wdtis) as u64 * 1000).abs_diff(wanted))
+        .unwrap_or(0);
+    Ok(WdtSetting {
+        wdtis,
+        timeout_ms: wdt_timeout_ms(clk_freq, wdtis),
+    })
+}
+
+/// Length of the next countdown of a timer delay of `remaining` ticks, when a countdown can last
+/// at most `max_chunk` ticks. A countdown is one tick longer than the count written to CCR0, and
+/// writing 0 to CCR0 stops the timer in up mode, so countdowns are always at least 2 ticks long. A
+/// delay of 1 tick is rounded up to 2 ticks, and longer delays are split so that the last
+/// countdown isn't a single tick.
+#[inline]
+pub fn delay_chunk(remaining: u32, max_chunk: u32) -> u32 {
+    if remaining <= max_chunk {
+        remaining.max(2)
+    } else if remaining - max_chunk == 1 {
+        max_chunk - 1
+    } else {
+        max_chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lengths of all countdowns of a delay
+    fn delay_chunks(mut ticks: u32, max_chunk: u32) -> impl Iterator<Item = u32> {
+        core::iter::from_fn(move || {
+            if ticks == 0 {
+                return None;
+            }
+            let chunk = delay_chunk(ticks, max_chunk);
+            ticks = ticks.saturating_sub(chunk);
+            Some(chunk)
+        })
+    }
+
+    #[test]
+    fn delay_single_tick() {
+        assert!(delay_chunks(1, 0x10000).eq([2]));
+        assert!(delay_chunks(1, 0x100).eq([2]));
+    }
+
+    #[test]
+    fn delay_one_past_max_chunk() {
+        assert!(delay_chunks(0x10001, 0x10000).eq([0xFFFF, 2]));
+        assert!(delay_chunks(0x101, 0x100).eq([0xFF, 2]));
+    }
+
+    #[test]
+    fn delay_chunks_sum_to_ticks() {
+        for max_chunk in [0x100, 0x400, 0x1000] {
+            for ticks in 2..(4 * max_chunk + 3) {
+                let mut total = 0;
+                for chunk in delay_chunks(ticks, max_chunk) {
+                    assert!((2..=max_chunk).contains(&chunk), "{} of {}", chunk, ticks);
+                    total += chunk;
+                }
+                assert_eq!(total, ticks);
+            }
+        }
+    }
+
+    #[test]
+    fn dco_freq_close_to_nominal() {
+        for sel in DcoclkFreqSel::ALL {
+            let freq = sel.freq();
+            let nominal = sel.nominal_freq();
+            // 1 MHz is 32 * 32768 Hz, the largest deviation
+            assert!(freq.abs_diff(nominal) * 20 <= nominal, "{:?}: {}", sel, freq);
+            // FLLN is a 10 bit field
+            assert!(sel.multiplier() - 1 <= 0x3FF);
+        }
+        for pair in DcoclkFreqSel::ALL.windows(2) {
+            assert!(pair[0].freq() < pair[1].freq());
+        }
+    }
+
+    #[test]
+    fn dco_at_most() {
+        assert_eq!(DcoclkFreqSel::at_most(999_999), None);
+        assert_eq!(DcoclkFreqSel::at_most(u32::MAX), Some(DcoclkFreqSel::_24MHz));
+        for sel in DcoclkFreqSel::ALL {
+            assert_eq!(DcoclkFreqSel::at_most(sel.nominal_freq()), Some(sel));
+            assert_eq!(DcoclkFreqSel::at_most(sel.nominal_freq() + 999_999), Some(sel));
+        }
+    }
+
+    #[test]
+    fn fram_wait_states() {
+        assert_eq!(WaitStates::for_mclk(REFOCLK as u32), WaitStates::Wait0);
+        assert_eq!(WaitStates::for_mclk(8_000_000), WaitStates::Wait0);
+        assert_eq!(WaitStates::for_mclk(8_000_001), WaitStates::Wait1);
+        assert_eq!(WaitStates::for_mclk(16_000_000), WaitStates::Wait1);
+        assert_eq!(WaitStates::for_mclk(16_000_001), WaitStates::Wait2);
+        assert_eq!(WaitStates::for_mclk(MCLK_MAX), WaitStates::Wait2);
+        for wait in [WaitStates::Wait0, WaitStates::Wait1, WaitStates::Wait2] {
+            assert_eq!(WaitStates::for_mclk(wait.max_mclk()), wait);
+        }
+        for sel in DcoclkFreqSel::ALL {
+            // The actual DCO frequency can be slightly above the nominal one
+            let wait = WaitStates::for_mclk(sel.freq());
+            assert!(sel.freq() <= wait.max_mclk() || wait == WaitStates::Wait2);
+        }
+    }
+
+    #[test]
+    fn brs_table() {
+        // Each fraction of the table maps to its own setting, and the fraction just below it maps
+        // to the previous setting
+        let bps = 10_000;
+        for (i, &(frac, brs)) in BRS_TABLE.iter().enumerate() {
+            assert_eq!(lookup_brs(5 * bps + frac as u32, bps), brs);
+            if i > 0 {
+                assert_eq!(lookup_brs(5 * bps + frac as u32 - 1, bps), BRS_TABLE[i - 1].1);
+            }
+        }
+        assert_eq!(lookup_brs(6 * bps - 1, bps), 0xFE);
+        // 2/3 rounds up to the 0.6667 entry
+        assert_eq!(lookup_brs(50_000, 30_000), 0xD6);
+    }
+
+    // Every row of the user's guide table of typical clock and baud rate settings, as (clock,
+    // baud rate, UCOS16, UCBRx, UCBRFx, UCBRSx). The guide picks its settings by searching for the
+    // lowest error, while the HAL computes them from the fractional part of the clock divider, so
+    // rows where the two differ expect the computed settings and note the guide's. The guide
+    // doesn't oversample some clocks of just over 16 times the baud rate, and picks a different
+    // UCBRSx for some fractions.
+    const TYPICAL: [(u32, u32, bool, u16, u8, u8); 68] = [
+        (32768, 1200, true, 1, 11, 0x25),
+        (32768, 2400, false, 13, 0, 0xB6),
+        (32768, 4800, false, 6, 0, 0xEE),
+        (32768, 9600, false, 3, 0, 0x92),
+        (1_000_000, 9600, true, 6, 8, 0x20),
+        (1_000_000, 19200, true, 3, 4, 0x02),
+        (1_000_000, 38400, true, 1, 10, 0x00),
+        (1_000_000, 57600, true, 1, 1, 0x4A), // guide: UCOS16 = 0, UCBRx = 17
+        (1_000_000, 115200, false, 8, 0, 0xD6),
+        (1_048_576, 9600, true, 6, 13, 0x22),
+        (1_048_576, 19200, true, 3, 6, 0xAD),
+        (1_048_576, 38400, true, 1, 11, 0x25), // guide: UCBRSx = 0xD6
+        (1_048_576, 57600, true, 1, 2, 0x11), // guide: UCOS16 = 0, UCBRx = 18
+        (1_048_576, 115200, false, 9, 0, 0x08),
+        (4_000_000, 9600, true, 26, 0, 0xD6), // guide: UCBRSx = 0xB6
+        (4_000_000, 19200, true, 13, 0, 0x25), // guide: UCBRSx = 0x84
+        (4_000_000, 38400, true, 6, 8, 0x20),
+        (4_000_000, 57600, true, 4, 5, 0x55),
+        (4_000_000, 115200, true, 2, 2, 0xBB),
+        (4_000_000, 230400, true, 1, 1, 0x4A), // guide: UCOS16 = 0, UCBRx = 17
+        (4_194_304, 9600, true, 27, 4, 0xFB),
+        (4_194_304, 19200, true, 13, 10, 0x55),
+        (4_194_304, 38400, true, 6, 13, 0x22),
+        (4_194_304, 57600, true, 4, 8, 0xEE),
+        (4_194_304, 115200, true, 2, 4, 0x92),
+        (4_194_304, 230400, true, 1, 2, 0x11), // guide: UCOS16 = 0, UCBRx = 18
+        (8_000_000, 9600, true, 52, 1, 0x25), // guide: UCBRSx = 0x49
+        (8_000_000, 19200, true, 26, 0, 0xD6), // guide: UCBRSx = 0xB6
+        (8_000_000, 38400, true, 13, 0, 0x25), // guide: UCBRSx = 0x84
+        (8_000_000, 57600, true, 8, 10, 0xF7),
+        (8_000_000, 115200, true, 4, 5, 0x55),
+        (8_000_000, 230400, true, 2, 2, 0xBB),
+        (8_000_000, 460800, true, 1, 1, 0x4A), // guide: UCOS16 = 0, UCBRx = 17
+        (8_388_608, 9600, true, 54, 9, 0xEE),
+        (8_388_608, 19200, true, 27, 4, 0xFB),
+        (8_388_608, 38400, true, 13, 10, 0x55),
+        (8_388_608, 57600, true, 9, 1, 0xB5),
+        (8_388_608, 115200, true, 4, 8, 0xEE),
+        (8_388_608, 230400, true, 2, 4, 0x92),
+        (8_388_608, 460800, true, 1, 2, 0x11), // guide: UCOS16 = 0, UCBRx = 18
+        (12_000_000, 9600, true, 78, 2, 0x00),
+        (12_000_000, 19200, true, 39, 1, 0x00),
+        (12_000_000, 38400, true, 19, 8, 0x55), // guide: UCBRSx = 0x65
+        (12_000_000, 57600, true, 13, 0, 0x25),
+        (12_000_000, 115200, true, 6, 8, 0x20),
+        (12_000_000, 230400, true, 3, 4, 0x02),
+        (12_000_000, 460800, true, 1, 10, 0x00),
+        (16_000_000, 9600, true, 104, 2, 0xD6),
+        (16_000_000, 19200, true, 52, 1, 0x25), // guide: UCBRSx = 0x49
+        (16_000_000, 38400, true, 26, 0, 0xD6), // guide: UCBRSx = 0xB6
+        (16_000_000, 57600, true, 17, 5, 0xDD),
+        (16_000_000, 115200, true, 8, 10, 0xF7),
+        (16_000_000, 230400, true, 4, 5, 0x55),
+        (16_000_000, 460800, true, 2, 2, 0xBB),
+        (16_777_216, 9600, true, 109, 3, 0xB5),
+        (16_777_216, 19200, true, 54, 9, 0xEE),
+        (16_777_216, 38400, true, 27, 4, 0xFB),
+        (16_777_216, 57600, true, 18, 3, 0x44),
+        (16_777_216, 115200, true, 9, 1, 0xB5),
+        (16_777_216, 230400, true, 4, 8, 0xEE),
+        (16_777_216, 460800, true, 2, 4, 0x92),
+        (20_000_000, 9600, true, 130, 3, 0x25),
+        (20_000_000, 19200, true, 65, 1, 0xD6),
+        (20_000_000, 38400, true, 32, 8, 0xBF), // guide: UCBRSx = 0xEE
+        (20_000_000, 57600, true, 21, 11, 0x21), // guide: UCBRSx = 0x22
+        (20_000_000, 115200, true, 10, 13, 0xAD),
+        (20_000_000, 230400, true, 5, 6, 0xEE),
+        (20_000_000, 460800, true, 2, 11, 0x92),
+    ];
+
+    #[test]
+    fn typical_baud_configs() {
+        for (clk, bps, ucos16, br, brf, brs) in TYPICAL {
+            let config = calculate_baud_config(clk, bps);
+            assert_eq!(
+                config,
+                BaudConfig {
+                    br,
+                    brs,
+                    brf,
+                    ucos16
+                },
+                "{} Hz, {} bps",
+                clk,
+                bps
+            );
+        }
+    }
+
+    #[test]
+    fn baudrate_round_trip() {
+        let clocks = [32768, 1_000_000, 1_048_576, 4_000_000, 8_000_000, 16_000_000, 24_000_000];
+        let rates = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200, 230400, 460800];
+        for clk in clocks {
+            for bps in rates {
+                // Too few clock periods per bit for an accurate rate
+                if clk / bps < 3 {
+                    continue;
+                }
+                let actual = calculate_baud_config(clk, bps).baudrate(clk);
+                assert!(
+                    actual.abs_diff(bps) * 100 <= bps * 3,
+                    "{} Hz, {} bps: {} bps",
+                    clk,
+                    bps,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_baud_configs() {
+        assert_eq!(calculate_baud_config(1_000_000, 0).br, 62500);
+        assert_eq!(calculate_baud_config(1_000, 9600).br, 1);
+    }
+
+    #[test]
+    fn wdt_refoclk_intervals() {
+        // Intervals at 32.768 kHz listed for each WDTIS setting
+        let expected = [65_536_000, 4_096_000, 256_000, 16_000, 1000, 250, 16, 2];
+        for (wdtis, &ms) in expected.iter().enumerate() {
+            assert_eq!(wdt_timeout_ms(REFOCLK as u32, wdtis as u8), ms);
+        }
+    }
+
+    #[test]
+    fn wdt_setting_round_trip() {
+        let clocks = [VLOCLK as u32, REFOCLK as u32, 1_000_000, 8_000_000, 16_000_000, MCLK_MAX];
+        for clk in clocks {
+            for wdtis in 0..8 {
+                let ms = wdt_timeout_ms(clk, wdtis);
+                // Rounding to milliseconds blurs the short intervals of fast clocks together
+                if ms < 4 {
+                    continue;
+                }
+                assert_eq!(
+                    wdt_setting(clk, ms),
+                    Ok(WdtSetting {
+                        wdtis,
+                        timeout_ms: ms
+                    }),
+                    "{} Hz, {} ms",
+                    clk,
+                    ms
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn wdt_setting_closest() {
+        // 2^13 and 2^15 periods of VLOCLK are 819.2 ms and 3276.8 ms
+        assert_eq!(wdt_setting(VLOCLK as u32, 2047).unwrap().wdtis, 5);
+        assert_eq!(wdt_setting(VLOCLK as u32, 2049).unwrap().wdtis, 4);
+        assert_eq!(wdt_setting(VLOCLK as u32, 1000).unwrap().timeout_ms, 819);
+    }
+
+    #[test]
+    fn wdt_setting_out_of_range() {
+        // The shortest interval of VLOCLK is 6.4 ms, the longest of 24 MHz is 89478.5 ms
+        assert!(wdt_setting(VLOCLK as u32, 5).is_err());
+        assert!(wdt_setting(VLOCLK as u32, 6).is_ok());
+        assert!(wdt_setting(MCLK_MAX, 0).is_err());
+        assert!(wdt_setting(MCLK_MAX, 89_478).is_ok());
+        assert!(wdt_setting(MCLK_MAX, 89_479).is_err());
+    }
+}
@@ -7,21 +7,22 @@
 //! be decided before any of them can be used. This differs from `Pwm`, where pins are initialized
 //! on an individual basis.
 
-use crate::gpio::{
-    Alternate2, Floating, Input, Pin, Pin0, Pin1, Pin2, Pin3, Pin4, Pin7,
-    P4, P5
-};
-use crate::hw_traits::timerb::{CCRn, Ccis, Cm};
-use crate::timer::{read_tbxiv, CapCmpTimer3, CapCmpTimer7, TimerVector};
+use crate::clock::{Aclk, AclkSource};
+use crate::gpio::{Alternate2, Floating, Input, Pin, Pin0, Pin1, Pin2, Pin3, Pin4, Pin7, P4, P5};
+use crate::hw_traits::timerb::{CCRn, Ccis, Cm, CounterLength, Tbssel};
+use crate::timer::{extend_count, read_tbxiv, CapCmpTimer3, CapCmpTimer7, TimerVector};
 use core::marker::PhantomData;
 use msp430fr247x as pac;
 
+pub mod measure;
+
 pub use crate::timer::{
     CapCmp, TimerConfig, TimerDiv, TimerExDiv, TimerPeriph, CCR0, CCR1, CCR2, CCR3, CCR4, CCR5,
     CCR6,
 };
 
 /// Capture edge trigger
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CapTrigger {
     /// Capture on rising edge
     RisingEdge,
@@ -107,16 +108,10 @@ impl CapturePeriph for pac::TB0 {
 //     type Gpio6 = Pin<P6, Pin5, Alternate1<Input<Floating>>>;
 // }
 
+// Capture input B of pins 0 and 1 is wired to internal signals, so those pins only get input A and
+// trigger methods here, and typed methods for their input B where the signal is known
 macro_rules! config_fn {
-    (methods $config_sel_b:ident, $config_trigger:ident, $pin:ident) => {
-        #[allow(non_snake_case)]
-        #[inline(always)]
-        /// Configure the capture input select of the capture pin as capture input B
-        pub fn $config_sel_b(mut self) -> Self {
-            self.$pin.select = Ccis::InputB;
-            self
-        }
-
+    (trigger $config_trigger:ident, $pin:ident) => {
         #[inline(always)]
         /// Configure the capture trigger event of the capture pin
         pub fn $config_trigger(mut self, trigger: CapTrigger) -> Self {
@@ -125,7 +120,7 @@ macro_rules! config_fn {
         }
     };
 
-    ($config_sel_a:ident, $config_sel_b:ident, $config_trigger:ident, $pin:ident, $gpio:ident) => {
+    (input_a $config_sel_a:ident, $pin:ident, $gpio:ident) => {
         #[allow(non_snake_case)]
         #[inline(always)]
         /// Configure the capture input select of the capture pin as capture input A, which
@@ -134,10 +129,9 @@ macro_rules! config_fn {
             self.$pin.select = Ccis::InputA;
             self
         }
-        config_fn!(methods $config_sel_b, $config_trigger, $pin);
     };
 
-    ($config_sel_a:ident, $config_sel_b:ident, $config_trigger:ident, $pin:ident) => {
+    (input_a $config_sel_a:ident, $pin:ident) => {
         #[allow(non_snake_case)]
         #[inline(always)]
         /// Configure the capture input select of the capture pin as capture input A
@@ -145,7 +139,20 @@ macro_rules! config_fn {
             self.$pin.select = Ccis::InputA;
             self
         }
-        config_fn!(methods $config_sel_b, $config_trigger, $pin);
+    };
+
+    ($config_sel_a:ident, $config_sel_b:ident, $config_trigger:ident, $pin:ident, $gpio:ident) => {
+        config_fn!(input_a $config_sel_a, $pin, $gpio);
+
+        #[allow(non_snake_case)]
+        #[inline(always)]
+        /// Configure the capture input select of the capture pin as capture input B
+        pub fn $config_sel_b(mut self) -> Self {
+            self.$pin.select = Ccis::InputB;
+            self
+        }
+
+        config_fn!(trigger $config_trigger, $pin);
     };
 }
 
@@ -181,19 +188,10 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureParts3<T> {
 }
 
 impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
-    config_fn!(
-        config_cap0_input_A,
-        config_cap0_input_B,
-        config_cap0_trigger,
-        cap0
-    );
-    config_fn!(
-        config_cap1_input_A,
-        config_cap1_input_B,
-        config_cap1_trigger,
-        cap1,
-        Gpio1
-    );
+    config_fn!(input_a config_cap0_input_A, cap0);
+    config_fn!(trigger config_cap0_trigger, cap0);
+    config_fn!(input_a config_cap1_input_A, cap1, Gpio1);
+    config_fn!(trigger config_cap1_trigger, cap1);
     config_fn!(
         config_cap2_input_A,
         config_cap2_input_B,
@@ -205,6 +203,7 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
     /// Writes all previously configured timer and capture settings into peripheral registers
     pub fn commit(self) -> CaptureParts3<T> {
         let timer = self.timer;
+        let clock = TimerClock::new(&self.config);
         self.config.write_regs(&timer);
         CCRn::<CCR0>::config_cap_mode(&timer, self.cap0.trigger.into(), self.cap0.select.into());
         CCRn::<CCR1>::config_cap_mode(&timer, self.cap1.trigger.into(), self.cap1.select.into());
@@ -212,9 +211,9 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
         timer.continuous();
 
         CaptureParts3 {
-            cap0: Capture::new(),
-            cap1: Capture::new(),
-            cap2: Capture::new(),
+            cap0: Capture::new(clock, self.cap0.trigger),
+            cap1: Capture::new(clock, self.cap1.trigger),
+            cap2: Capture::new(clock, self.cap2.trigger),
             tbxiv: TBxIV(PhantomData),
         }
     }
@@ -227,6 +226,9 @@ impl<T: CapturePeriph + CapCmpTimer3> CaptureConfig3<T> {
 /// capture trigger event, which determines the input transitions that actually trigger the
 /// capture. By default, all pins use GND as their input source and trigger a capture on a rising
 /// edge.
+///
+/// Capture input B of pins 0 and 1 is connected to internal signals. On TB0, these are ACLK and
+/// the eCOMP0 output, selected with `config_cap0_input_aclk` and `config_cap1_input_ecomp0`.
 pub struct CaptureConfig7<T: CapturePeriph>
 where
     T: CapCmpTimer7,
@@ -260,19 +262,10 @@ impl<T: CapturePeriph + CapCmpTimer7> CaptureParts7<T> {
 }
 
 impl<T: CapturePeriph + CapCmpTimer7> CaptureConfig7<T> {
-    config_fn!(
-        config_cap0_input_A,
-        config_cap0_input_B,
-        config_cap0_trigger,
-        cap0
-    );
-    config_fn!(
-        config_cap1_input_A,
-        config_cap1_input_B,
-        config_cap1_trigger,
-        cap1,
-        Gpio1
-    );
+    config_fn!(input_a config_cap0_input_A, cap0);
+    config_fn!(trigger config_cap0_trigger, cap0);
+    config_fn!(input_a config_cap1_input_A, cap1, Gpio1);
+    config_fn!(trigger config_cap1_trigger, cap1);
     config_fn!(
         config_cap2_input_A,
         config_cap2_input_B,
@@ -312,6 +305,7 @@ impl<T: CapturePeriph + CapCmpTimer7> CaptureConfig7<T> {
     /// Writes all previously configured timer and capture settings into peripheral registers
     pub fn commit(self) -> CaptureParts7<T> {
         let timer = self.timer;
+        let clock = TimerClock::new(&self.config);
         self.config.write_regs(&timer);
         CCRn::<CCR0>::config_cap_mode(&timer, self.cap0.trigger.into(), self.cap0.select.into());
         CCRn::<CCR1>::config_cap_mode(&timer, self.cap1.trigger.into(), self.cap1.select.into());
@@ -323,18 +317,37 @@ impl<T: CapturePeriph + CapCmpTimer7> CaptureConfig7<T> {
         timer.continuous();
 
         CaptureParts7 {
-            cap0: Capture::new(),
-            cap1: Capture::new(),
-            cap2: Capture::new(),
-            cap3: Capture::new(),
-            cap4: Capture::new(),
-            cap5: Capture::new(),
-            cap6: Capture::new(),
+            cap0: Capture::new(clock, self.cap0.trigger),
+            cap1: Capture::new(clock, self.cap1.trigger),
+            cap2: Capture::new(clock, self.cap2.trigger),
+            cap3: Capture::new(clock, self.cap3.trigger),
+            cap4: Capture::new(clock, self.cap4.trigger),
+            cap5: Capture::new(clock, self.cap5.trigger),
+            cap6: Capture::new(clock, self.cap6.trigger),
             tbxiv: TBxIV(PhantomData),
         }
     }
 }
 
+impl CaptureConfig7<pac::TB0> {
+    /// Configure capture pin 0 to capture ACLK, which is internally connected to its capture
+    /// input B. Used to measure ACLK against the timer clock or vice versa, such as for calibrating
+    /// VLOCLK or the DCO.
+    #[inline(always)]
+    pub fn config_cap0_input_aclk<SRC: AclkSource>(mut self, _aclk: &Aclk<SRC>) -> Self {
+        self.cap0.select = Ccis::InputB;
+        self
+    }
+
+    /// Configure capture pin 1 to capture the eCOMP0 comparator output, which is internally
+    /// connected to its capture input B. The comparator must be configured separately.
+    #[inline(always)]
+    pub fn config_cap1_input_ecomp0(mut self) -> Self {
+        self.cap1.select = Ccis::InputB;
+        self
+    }
+}
+
 /// Collection of capture pins derived from timer peripheral with 3 capture-compare registers
 pub struct CaptureParts3<T: CapCmpTimer3> {
     /// Capture pin 0 (derived from capture-compare register 0)
@@ -367,12 +380,73 @@ pub struct CaptureParts7<T: CapCmpTimer7> {
     pub tbxiv: TBxIV<T>,
 }
 
+// Timer clock settings taken from the `TimerConfig` used to create the capture pins
+#[derive(Clone, Copy)]
+struct TimerClock {
+    tick_freq: u32,
+    cntl: CounterLength,
+    sel: Tbssel,
+    div: u32,
+}
+
+impl TimerClock {
+    #[inline(always)]
+    fn new<T: TimerPeriph>(config: &TimerConfig<T>) -> Self {
+        TimerClock {
+            tick_freq: config.tick_freq(),
+            cntl: config.cntl,
+            sel: config.sel,
+            div: config.total_div(),
+        }
+    }
+}
+
 /// Single capture pin with its own capture register
-pub struct Capture<T: CapCmp<C>, C>(PhantomData<T>, PhantomData<C>);
+pub struct Capture<T: CapCmp<C>, C> {
+    _timer: PhantomData<T>,
+    _ccrn: PhantomData<C>,
+    clock: TimerClock,
+    trigger: CapTrigger,
+}
 
 impl<T: CapCmp<C>, C> Capture<T, C> {
-    fn new() -> Self {
-        Self(PhantomData, PhantomData)
+    fn new(clock: TimerClock, trigger: CapTrigger) -> Self {
+        Self {
+            _timer: PhantomData,
+            _ccrn: PhantomData,
+            clock,
+            trigger,
+        }
+    }
+
+    /// Frequency of the timer ticks, as derived from the `TimerConfig` used to create the capture
+    #[inline(always)]
+    pub fn tick_freq(&self) -> u32 {
+        self.clock.tick_freq
+    }
+
+    /// Length of the timer counter, as configured by the `TimerConfig` used to create the capture
+    #[inline(always)]
+    pub fn counter_length(&self) -> CounterLength {
+        self.clock.cntl
+    }
+
+    // Clock source of the timer
+    #[inline(always)]
+    pub(crate) fn clock_source(&self) -> Tbssel {
+        self.clock.sel
+    }
+
+    // Combined division of the timer clock dividers
+    #[inline(always)]
+    pub(crate) fn clk_div(&self) -> u32 {
+        self.clock.div
+    }
+
+    /// Capture trigger event the pin was configured with
+    #[inline(always)]
+    pub fn trigger(&self) -> CapTrigger {
+        self.trigger
     }
 }
 
@@ -417,6 +491,49 @@ impl<T: CapCmp<C>, C> CapturePin for Capture<T, C> {
 }
 
 impl<T: CapCmp<C>, C> Capture<T, C> {
+    /// Capture the timer count in software, for timestamping events that don't arrive on a capture
+    /// pin, such as from a GPIO interrupt. The count is latched by the capture hardware on the next
+    /// timer clock, so it isn't affected by how long the read takes.
+    ///
+    /// The capture is triggered by switching the capture input from GND to VCC, after which the
+    /// configured input and trigger are restored, so this works with any pin configuration. The
+    /// capture interrupt is masked during the call, and an unread capture is kept along with its
+    /// flags, so it can still be read or handled afterwards. Pin edges arriving during the call
+    /// aren't captured.
+    #[inline]
+    pub fn software_capture(&mut self) -> u16 {
+        let timer = unsafe { T::steal() };
+        // Keep the interrupt handler from taking the software capture
+        let ccie = timer.ccie_rd();
+        timer.ccie_clr();
+        let (cm, ccis) = timer.cap_sel_rd();
+        // Switch inputs with captures disabled, so that only the GND to VCC edge is captured
+        timer.config_cap_sel(Cm::NoCap, ccis);
+        let (cov, ccifg) = timer.cov_ccifg_rd();
+        let pending = timer.get_ccrn();
+        timer.config_cap_sel(Cm::NoCap, Ccis::Gnd);
+        timer.config_cap_sel(Cm::BothEdges, Ccis::Gnd);
+        timer.cov_ccifg_clr();
+        timer.config_cap_sel(Cm::BothEdges, Ccis::Vcc);
+        while !timer.ccifg_rd() {}
+        let ccrn = timer.get_ccrn();
+        timer.config_cap_sel(Cm::NoCap, ccis);
+        // Put back the unread capture
+        timer.cov_ccifg_clr();
+        timer.set_ccrn(pending);
+        if cov {
+            timer.cov_set();
+        }
+        if ccifg {
+            timer.ccifg_set();
+        }
+        timer.config_cap_sel(cm, ccis);
+        if ccie {
+            timer.ccie_set();
+        }
+        ccrn
+    }
+
     #[inline]
     /// Enable capture interrupts
     pub fn enable_interrupts(&mut self) {
@@ -432,6 +549,19 @@ impl<T: CapCmp<C>, C> Capture<T, C> {
     }
 }
 
+impl<T: TimerPeriph + CapCmp<C>, C> Capture<T, C> {
+    /// Timestamp the current instant with a software capture, extended to 32 bits with the number
+    /// of timer overflows counted so far, such as by handling the `CaptureVector::MainTimer`
+    /// interrupt. A pending overflow that hasn't been counted yet is taken into account, but not
+    /// cleared.
+    #[inline]
+    pub fn timestamp(&mut self, overflows: u32) -> u32 {
+        let count = self.software_capture();
+        let timer = unsafe { T::steal() };
+        extend_count(&timer, self.clock.cntl, count, overflows)
+    }
+}
+
 /// Error returned when the previous capture was overwritten before being read
 pub struct OverCapture(pub u16);
 
@@ -0,0 +1,347 @@
+//! Signal measurement on top of capture pins
+//!
+//! `Measure` wraps a `Capture` pin and turns its raw timestamps into periods, frequencies, pulse
+//! widths and duty cycles. Timestamps are extended to 32 bits in software by counting timer
+//! overflows, so signals slower than the counter period can also be measured.
+//!
+//! Overflows are counted by polling TBIFG while waiting for captures, so the timer's overflow
+//! flag must not be used elsewhere. Period measurements work with any capture trigger. Pulse width
+//! and duty cycle measurements require the capture pin to trigger on both edges, and return
+//! `PulseError::NotBothEdges` otherwise.
+//!
+//! The polarity of each edge comes from the capture trigger. When capturing both edges, the input
+//! level is sampled once before the first edge, and the following edges are taken to alternate,
+//! which holds as long as no capture is overwritten. Signals whose edges come too fast for the
+//! level to be sampled between them fail with `OverCapture`.
+//!
+//! All measurements block until enough edges have been captured.
+//!
+//! With capture pin 0 of TB0 capturing ACLK, `Aclk<Vlo>::calibrate` measures the actual VLOCLK
+//! frequency against the timer clock, and `Smclk::calibrate` measures the actual SMCLK frequency
+//! against a REFOCLK-sourced ACLK. Both store the measured frequency in the clock object, so
+//! peripherals configured afterwards use it.
+
+use super::{CapCmp, CapTrigger, Capture, OverCapture, TimerPeriph, CCR0};
+use crate::clock::{Aclk, Clock, Refo, Smclk, Vlo};
+use crate::hw_traits::timerb::{CCRn, Tbssel};
+
+/// Signal edge captured with its 32-bit extended timestamp
+#[derive(Clone, Copy)]
+pub struct Edge {
+    /// Timer count at the edge, extended in software with the number of timer overflows
+    pub timestamp: u32,
+    /// Whether the edge was rising
+    pub rising: bool,
+}
+
+/// Pulse measurement of a periodic signal, in timer ticks
+#[derive(Clone, Copy)]
+pub struct Pulse {
+    /// Length of a full period
+    pub period: u32,
+    /// Time the signal spends high in each period
+    pub high: u32,
+}
+
+impl Pulse {
+    /// Duty cycle in thousandths of the period
+    #[inline]
+    pub fn duty_permille(&self) -> u16 {
+        match self.period {
+            0 => 0,
+            period => (self.high as u64 * 1000 / period as u64).min(1000) as u16,
+        }
+    }
+}
+
+/// Error returned by pulse width and duty cycle measurements
+pub enum PulseError {
+    /// The capture pin doesn't trigger on both edges, so the pulse edges can't be told apart
+    NotBothEdges,
+    /// A capture was overwritten before being read
+    OverCapture(OverCapture),
+}
+
+impl From<OverCapture> for PulseError {
+    #[inline]
+    fn from(err: OverCapture) -> Self {
+        PulseError::OverCapture(err)
+    }
+}
+
+// Attempts at sampling the input level without an edge before giving up
+const SYNC_ATTEMPTS: u8 = 4;
+
+/// Measurement layer over a capture pin
+pub struct Measure<T: TimerPeriph + CapCmp<C>, C> {
+    capture: Capture<T, C>,
+    overflows: u32,
+    next_rising: bool,
+}
+
+impl<T: TimerPeriph + CapCmp<C>, C> Measure<T, C> {
+    /// Wrap a capture pin for measurements
+    #[inline]
+    pub fn new(capture: Capture<T, C>) -> Self {
+        Measure {
+            capture,
+            overflows: 0,
+            next_rising: true,
+        }
+    }
+
+    /// Release the capture pin
+    #[inline]
+    pub fn free(self) -> Capture<T, C> {
+        self.capture
+    }
+
+    /// Frequency of the timer ticks, used to convert measurements into Hertz
+    #[inline]
+    pub fn tick_freq(&self) -> u32 {
+        self.capture.tick_freq()
+    }
+
+    // Discard stale captures and overflows, since their timestamps can't be extended anymore, and
+    // find the polarity of the next edge when capturing both edges. Fails if edges keep coming
+    // while the level is sampled.
+    fn sync(&mut self) -> Result<(), OverCapture> {
+        let timer = unsafe { T::steal() };
+        timer.tbifg_clr();
+        self.overflows = 0;
+        for _ in 0..SYNC_ATTEMPTS {
+            CCRn::<C>::cov_ccifg_clr(&timer);
+            let level = CCRn::<C>::cci_rd(&timer);
+            // Synchronized captures are only flagged on the next timer clock, so give an edge
+            // that happened around the level read enough time to show up
+            for _ in 0..2 {
+                let count = timer.tbxr_rd();
+                while timer.tbxr_rd() == count {}
+            }
+            if !CCRn::<C>::ccifg_rd(&timer) {
+                self.next_rising = !level;
+                return Ok(());
+            }
+        }
+        let count = CCRn::<C>::get_ccrn(&timer);
+        CCRn::<C>::cov_ccifg_clr(&timer);
+        Err(OverCapture(count))
+    }
+
+    // Fail unless the capture pin triggers on both edges
+    #[inline]
+    fn check_both_edges(&self) -> Result<(), PulseError> {
+        match self.capture.trigger() {
+            CapTrigger::BothEdges => Ok(()),
+            _ => Err(PulseError::NotBothEdges),
+        }
+    }
+
+    // Polarity of the edge that was just captured
+    #[inline]
+    fn edge_polarity(&mut self) -> bool {
+        match self.capture.trigger() {
+            CapTrigger::RisingEdge => true,
+            CapTrigger::FallingEdge => false,
+            CapTrigger::BothEdges => {
+                let rising = self.next_rising;
+                self.next_rising = !rising;
+                rising
+            }
+        }
+    }
+
+    /// Wait for the next captured edge. Timestamps are only comparable to those of other edges
+    /// within the same measurement.
+    pub fn next_edge(&mut self) -> Result<Edge, OverCapture> {
+        let timer = unsafe { T::steal() };
+        loop {
+            // Sample the overflow flag before the capture flag, so an overflow seen without a
+            // capture happened before any capture still to come
+            let overflowed = timer.tbifg_rd();
+            let (cov, ccifg) = CCRn::<C>::cov_ccifg_rd(&timer);
+            if ccifg {
+                let count = CCRn::<C>::get_ccrn(&timer);
+                CCRn::<C>::cov_ccifg_clr(&timer);
+                let rising = self.edge_polarity();
+                if cov {
+                    return Err(OverCapture(count));
+                }
+
+                // An overflow flagged alongside a capture in the lower half of the count happened
+                // before the capture, so it's counted now. Otherwise it happened after the capture
+                // and is left for the next edge.
+                let cntl = self.capture.counter_length();
+                if timer.tbifg_rd() && cntl.overflowed_before(count) {
+                    timer.tbifg_clr();
+                    self.overflows = self.overflows.wrapping_add(1);
+                }
+                return Ok(Edge {
+                    timestamp: cntl.extend(count, self.overflows),
+                    rising,
+                });
+            }
+
+            if overflowed {
+                timer.tbifg_clr();
+                self.overflows = self.overflows.wrapping_add(1);
+            }
+        }
+    }
+
+    // Wait for the next edge with the given polarity
+    fn next_edge_rising(&mut self, rising: bool) -> Result<Edge, OverCapture> {
+        loop {
+            let edge = self.next_edge()?;
+            if edge.rising == rising {
+                return Ok(edge);
+            }
+        }
+    }
+
+    /// Measure the period of the signal in timer ticks
+    #[inline]
+    pub fn period_ticks(&mut self) -> Result<u32, OverCapture> {
+        self.period_ticks_avg(1)
+    }
+
+    // Total time spanned by `n` consecutive periods, in timer ticks
+    fn span_ticks(&mut self, n: u16) -> Result<u32, OverCapture> {
+        self.sync()?;
+        let first = self.next_edge()?;
+        let mut last = first;
+        for _ in 0..n {
+            last = self.next_edge_rising(first.rising)?;
+        }
+        Ok(last.timestamp.wrapping_sub(first.timestamp))
+    }
+
+    /// Measure the period of the signal in timer ticks, averaged over `n` consecutive periods
+    pub fn period_ticks_avg(&mut self, n: u16) -> Result<u32, OverCapture> {
+        let n = n.max(1);
+        Ok(self.span_ticks(n)? / n as u32)
+    }
+
+    /// Measure the frequency of the signal in Hertz
+    #[inline]
+    pub fn frequency(&mut self) -> Result<u32, OverCapture> {
+        self.frequency_avg(1)
+    }
+
+    /// Measure the frequency of the signal in Hertz, averaged over `n` consecutive periods
+    pub fn frequency_avg(&mut self, n: u16) -> Result<u32, OverCapture> {
+        let n = n.max(1);
+        // Dividing the total time instead of the averaged period keeps the fractional ticks
+        let total = self.span_ticks(n)? as u64;
+        Ok((self.tick_freq() as u64 * n as u64)
+            .checked_div(total)
+            .unwrap_or(0) as u32)
+    }
+
+    /// Measure the width of a high pulse in timer ticks. Requires captures on both edges.
+    pub fn pulse_width_ticks(&mut self) -> Result<u32, PulseError> {
+        self.check_both_edges()?;
+        self.sync()?;
+        let rise = self.next_edge_rising(true)?;
+        let fall = self.next_edge_rising(false)?;
+        Ok(fall.timestamp.wrapping_sub(rise.timestamp))
+    }
+
+    /// Measure the period and high time of the signal. Requires captures on both edges.
+    #[inline]
+    pub fn pulse(&mut self) -> Result<Pulse, PulseError> {
+        self.pulse_avg(1)
+    }
+
+    /// Measure the period and high time of the signal, averaged over `n` consecutive periods.
+    /// Requires captures on both edges.
+    pub fn pulse_avg(&mut self, n: u16) -> Result<Pulse, PulseError> {
+        self.check_both_edges()?;
+        let n = n.max(1);
+        self.sync()?;
+        let first = self.next_edge_rising(true)?;
+        let mut rise = first;
+        let mut high = 0u32;
+        for _ in 0..n {
+            let fall = self.next_edge_rising(false)?;
+            high = high.wrapping_add(fall.timestamp.wrapping_sub(rise.timestamp));
+            rise = self.next_edge_rising(true)?;
+        }
+        Ok(Pulse {
+            period: rise.timestamp.wrapping_sub(first.timestamp) / n as u32,
+            high: high / n as u32,
+        })
+    }
+
+    /// Measure the duty cycle of the signal in thousandths of the period. Requires captures on
+    /// both edges.
+    #[inline]
+    pub fn duty_permille(&mut self) -> Result<u16, PulseError> {
+        Ok(self.pulse()?.duty_permille())
+    }
+}
+
+/// Clock calibration, with capture pin 0 configured to capture ACLK through
+/// `CaptureConfig7::config_cap0_input_aclk`
+impl<T: TimerPeriph> Measure<T, CCR0> {
+    /// Measure the ACLK frequency against the timer clock, averaged over `n` ACLK periods. With
+    /// ACLK sourced from VLOCLK and the timer clocked from SMCLK, this measures the actual VLOCLK
+    /// frequency.
+    #[inline]
+    pub fn aclk_freq(&mut self, n: u16) -> Result<u32, OverCapture> {
+        self.frequency_avg(n)
+    }
+
+    /// Measure the timer clock frequency against ACLK with a known frequency, averaged over `n`
+    /// ACLK periods. With ACLK sourced from REFOCLK and the timer clocked from SMCLK, this
+    /// measures the actual DCO-derived SMCLK frequency, before the timer clock dividers.
+    pub fn timer_clk_freq(&mut self, aclk_freq: u32, n: u16) -> Result<u32, OverCapture> {
+        let n = n.max(1);
+        let total = self.span_ticks(n)? as u64;
+        let div = self.capture.clk_div() as u64;
+        Ok((total * div * aclk_freq as u64 / n as u64) as u32)
+    }
+}
+
+impl Aclk<Vlo> {
+    /// Measure the actual VLOCLK frequency against the timer clock, averaged over `n` ACLK
+    /// periods, and use it as the ACLK frequency from now on. Capture pin 0 must capture ACLK
+    /// through `CaptureConfig7::config_cap0_input_aclk`, and the timer should run from an accurate
+    /// clock, such as SMCLK derived from the FLL-stabilized DCO. Returns the measured frequency.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer is clocked from ACLK, which can't be measured against itself.
+    pub fn calibrate<T: TimerPeriph>(
+        &mut self,
+        measure: &mut Measure<T, CCR0>,
+        n: u16,
+    ) -> Result<u16, OverCapture> {
+        assert!(measure.capture.clock_source() != Tbssel::Aclk);
+        let freq = measure.aclk_freq(n)?.min(u16::MAX as u32) as u16;
+        self.0 = freq;
+        Ok(freq)
+    }
+}
+
+impl Smclk {
+    /// Measure the actual SMCLK frequency against a REFOCLK-sourced ACLK, averaged over `n` ACLK
+    /// periods, and use it as the SMCLK frequency from now on. Capture pin 0 must capture ACLK
+    /// through `CaptureConfig7::config_cap0_input_aclk`, and the timer must be clocked from
+    /// SMCLK. Returns the measured frequency.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer isn't clocked from SMCLK.
+    pub fn calibrate<T: TimerPeriph>(
+        &mut self,
+        measure: &mut Measure<T, CCR0>,
+        aclk: &Aclk<Refo>,
+        n: u16,
+    ) -> Result<u32, OverCapture> {
+        assert!(measure.capture.clock_source() == Tbssel::Smclk);
+        let freq = measure.timer_clk_freq(aclk.freq() as u32, n)?;
+        self.0 = freq;
+        Ok(freq)
+    }
+}
@@ -1,26 +1,30 @@
 //! Clock system for configuration of MCLK, SMCLK, and ACLK.
 //!
 //! Once configuration is complete, `Aclk` and `Smclk` clock objects are returned. The clock
-//! objects are used to set the clock sources on other peripherals.
+//! objects are used to set the clock sources on other peripherals. `freeze_with_delay` also
+//! returns a `Delay` derived from the MCLK frequency.
 //! Configuration of MCLK and SMCLK *must* occur, though SMCLK can be disabled. In that case, only
 //! `Aclk` is returned.
 //!
 //! DCO with FLL is supported on MCLK for select frequencies. Supporting arbitrary frequencies on
 //! the DCO requires complex calibration routines not supported by the HAL.
+//!
+//! The ACLK source is part of the `Aclk` type, so the calibration routines in
+//! `capture::measure` can only measure a VLOCLK-sourced ACLK, or use a REFOCLK-sourced ACLK as
+//! their reference.
 
 use core::arch::asm;
+use core::marker::PhantomData;
 
-use crate::fram::{Fram, WaitStates};
+use crate::calc::WaitStates;
+use crate::delay::Delay;
+use crate::fram::Fram;
 use msp430fr247x as pac;
 use pac::cs::csctl1::DCORSEL_A;
 use pac::cs::csctl4::{SELA_A, SELMS_A};
+pub use crate::calc::{DcoclkFreqSel, REFOCLK, VLOCLK};
 pub use pac::cs::csctl5::{DIVM_A as MclkDiv, DIVS_A as SmclkDiv};
 
-/// REFOCLK frequency
-pub const REFOCLK: u16 = 32768;
-/// VLOCLK frequency
-pub const VLOCLK: u16 = 10000;
-
 enum MclkSel {
     Refoclk,
     Vloclk,
@@ -47,50 +51,34 @@ impl MclkSel {
     }
 }
 
-#[derive(Clone, Copy)]
-enum AclkSel {
-    Vloclk,
-    Refoclk,
+/// ACLK source typestate for the internal low-power, low-accuracy VLOCLK oscillator
+pub struct Vlo;
+/// ACLK source typestate for the internal trimmed REFOCLK oscillator
+pub struct Refo;
+
+/// Source of ACLK
+pub trait AclkSource: sealed::SealedAclkSource {
+    /// Nominal frequency of the source
+    const FREQ: u16;
+    #[doc(hidden)]
+    const SELA: SELA_A;
 }
 
-impl AclkSel {
-    #[inline(always)]
-    fn sela(self) -> SELA_A {
-        match self {
-            AclkSel::Vloclk => SELA_A::VLOCLK,
-            AclkSel::Refoclk => SELA_A::REFOCLK,
-        }
-    }
+impl AclkSource for Vlo {
+    const FREQ: u16 = VLOCLK;
+    const SELA: SELA_A = SELA_A::VLOCLK;
+}
 
-    #[inline(always)]
-    fn freq(self) -> u16 {
-        match self {
-            AclkSel::Vloclk => VLOCLK,
-            AclkSel::Refoclk => REFOCLK,
-        }
-    }
+impl AclkSource for Refo {
+    const FREQ: u16 = REFOCLK;
+    const SELA: SELA_A = SELA_A::REFOCLK;
 }
 
-/// Selectable DCOCLK frequencies when using factory trim settings.
-/// Actual frequencies may be slightly higher.
-#[derive(Clone, Copy)]
-pub enum DcoclkFreqSel {
-    /// 1 MHz
-    _1MHz,
-    /// 2 MHz
-    _2MHz,
-    /// 4 MHz
-    _4MHz,
-    /// 8 MHz
-    _8MHz,
-    /// 12 MHz
-    _12MHz,
-    /// 16 MHz
-    _16MHz,
-    /// 20 MHz
-    _20MHz,
-    /// 24 MHz
-    _24MHz,
+mod sealed {
+    pub trait SealedAclkSource {}
+
+    impl SealedAclkSource for super::Vlo {}
+    impl SealedAclkSource for super::Refo {}
 }
 
 impl DcoclkFreqSel {
@@ -107,26 +95,6 @@ impl DcoclkFreqSel {
             DcoclkFreqSel::_24MHz => DCORSEL_A::DCORSEL_7,
         }
     }
-
-    #[inline(always)]
-    fn multiplier(self) -> u16 {
-        match self {
-            DcoclkFreqSel::_1MHz => 32,
-            DcoclkFreqSel::_2MHz => 61,
-            DcoclkFreqSel::_4MHz => 122,
-            DcoclkFreqSel::_8MHz => 245,
-            DcoclkFreqSel::_12MHz => 366,
-            DcoclkFreqSel::_16MHz => 490,
-            DcoclkFreqSel::_20MHz => 610,
-            DcoclkFreqSel::_24MHz => 732,
-        }
-    }
-
-    /// Numerical frequency
-    #[inline]
-    pub fn freq(self) -> u32 {
-        (self.multiplier() as u32) * (REFOCLK as u32)
-    }
 }
 
 /// Typestate for `ClockConfig` that represents unconfigured clocks
@@ -163,11 +131,11 @@ impl SmclkState for SmclkDisabled {
 ///
 /// Can only commit configurations to hardware if both MCLK and SMCLK settings have been
 /// configured. ACLK configurations are optional, with its default source being REFOCLK.
-pub struct ClockConfig<MCLK, SMCLK> {
+pub struct ClockConfig<MCLK, SMCLK, ACLK: AclkSource = Refo> {
     periph: pac::CS,
     mclk: MCLK,
     mclk_div: MclkDiv,
-    aclk_sel: AclkSel,
+    _aclk: PhantomData<ACLK>,
     smclk: SMCLK,
 }
 
@@ -177,7 +145,7 @@ macro_rules! make_clkconf {
             periph: $conf.periph,
             mclk: $mclk,
             mclk_div: $conf.mclk_div,
-            aclk_sel: $conf.aclk_sel,
+            _aclk: PhantomData,
             smclk: $smclk,
         }
     };
@@ -191,29 +159,27 @@ impl ClockConfig<NoClockDefined, NoClockDefined> {
             smclk: NoClockDefined,
             mclk: NoClockDefined,
             mclk_div: MclkDiv::_1,
-            aclk_sel: AclkSel::Refoclk,
+            _aclk: PhantomData,
         }
     }
 }
 
-impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
+impl<MCLK, SMCLK, ACLK: AclkSource> ClockConfig<MCLK, SMCLK, ACLK> {
     /// Select REFOCLK for ACLK
     #[inline]
-    pub fn aclk_refoclk(mut self) -> Self {
-        self.aclk_sel = AclkSel::Refoclk;
-        self
+    pub fn aclk_refoclk(self) -> ClockConfig<MCLK, SMCLK, Refo> {
+        make_clkconf!(self, self.mclk, self.smclk)
     }
 
     /// Select VLOCLK for ACLK
     #[inline]
-    pub fn aclk_vloclk(mut self) -> Self {
-        self.aclk_sel = AclkSel::Vloclk;
-        self
+    pub fn aclk_vloclk(self) -> ClockConfig<MCLK, SMCLK, Vlo> {
+        make_clkconf!(self, self.mclk, self.smclk)
     }
 
     /// Select REFOCLK for MCLK and set the MCLK divider. Frequency is `10000 / mclk_div` Hz.
     #[inline]
-    pub fn mclk_refoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK> {
+    pub fn mclk_refoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, ACLK> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Refoclk), self.smclk)
@@ -222,7 +188,7 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
 
     /// Select VLOCLK for MCLK and set the MCLK divider. Frequency is `32768 / mclk_div` Hz.
     #[inline]
-    pub fn mclk_vcoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK> {
+    pub fn mclk_vcoclk(self, mclk_div: MclkDiv) -> ClockConfig<MclkDefined, SMCLK, ACLK> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Vloclk), self.smclk)
@@ -237,7 +203,7 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
         self,
         target_freq: DcoclkFreqSel,
         mclk_div: MclkDiv,
-    ) -> ClockConfig<MclkDefined, SMCLK> {
+    ) -> ClockConfig<MclkDefined, SMCLK, ACLK> {
         ClockConfig {
             mclk_div,
             ..make_clkconf!(self, MclkDefined(MclkSel::Dcoclk(target_freq)), self.smclk)
@@ -246,13 +212,13 @@ impl<MCLK, SMCLK> ClockConfig<MCLK, SMCLK> {
 
     /// Enable SMCLK and set SMCLK divider, which divides the MCLK frequency
     #[inline]
-    pub fn smclk_on(self, div: SmclkDiv) -> ClockConfig<MCLK, SmclkDefined> {
+    pub fn smclk_on(self, div: SmclkDiv) -> ClockConfig<MCLK, SmclkDefined, ACLK> {
         make_clkconf!(self, self.mclk, SmclkDefined(div))
     }
 
     /// Disable SMCLK
     #[inline]
-    pub fn smclk_off(self) -> ClockConfig<MCLK, SmclkDisabled> {
+    pub fn smclk_off(self) -> ClockConfig<MCLK, SmclkDisabled, ACLK> {
         make_clkconf!(self, self.mclk, SmclkDisabled)
     }
 }
@@ -269,7 +235,7 @@ fn fll_on() {
     unsafe { asm!("bic.b 64, SR", options(nomem, nostack)) };
 }
 
-impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
+impl<SMCLK: SmclkState, ACLK: AclkSource> ClockConfig<MclkDefined, SMCLK, ACLK> {
     #[inline]
     fn configure_dco_fll(&self) {
         // Run FLL configuration procedure from the user's guide if we are using DCO
@@ -300,7 +266,7 @@ impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
         // Configure clock selector and divisors
         self.periph.csctl4.write(|w| {
             w.sela()
-                .variant(self.aclk_sel.sela())
+                .variant(ACLK::SELA)
                 .selms()
                 .variant(self.mclk.0.selms())
         });
@@ -316,47 +282,58 @@ impl<SMCLK: SmclkState> ClockConfig<MclkDefined, SMCLK> {
 
     #[inline]
     unsafe fn configure_fram(fram: &mut Fram, mclk_freq: u32) {
-        if mclk_freq > 16_000_000 {
-            fram.set_wait_states(WaitStates::Wait2);
-        } else if mclk_freq > 8_000_000 {
-            fram.set_wait_states(WaitStates::Wait1);
-        } else {
-            fram.set_wait_states(WaitStates::Wait0);
-        }
+        fram.set_wait_states(WaitStates::for_mclk(mclk_freq));
     }
 }
 
-impl ClockConfig<MclkDefined, SmclkDefined> {
+impl<ACLK: AclkSource> ClockConfig<MclkDefined, SmclkDefined, ACLK> {
     /// Apply clock configuration to hardware and return SMCLK and ACLK clock objects
     #[inline]
-    pub fn freeze(self, fram: &mut Fram) -> (Smclk, Aclk) {
+    pub fn freeze(self, fram: &mut Fram) -> (Smclk, Aclk<ACLK>) {
         let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
         unsafe { Self::configure_fram(fram, mclk_freq) };
         self.configure_dco_fll();
         self.configure_cs();
         (
             Smclk(mclk_freq >> (self.smclk.0 as u32)),
-            Aclk(self.aclk_sel.freq()),
+            Aclk(ACLK::FREQ, PhantomData),
         )
     }
+
+    /// Apply clock configuration to hardware and return SMCLK and ACLK clock objects, as well as a
+    /// cycle-counted delay provider based on the MCLK frequency
+    #[inline]
+    pub fn freeze_with_delay(self, fram: &mut Fram) -> (Smclk, Aclk<ACLK>, Delay) {
+        let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
+        let (smclk, aclk) = self.freeze(fram);
+        (smclk, aclk, Delay::new(mclk_freq))
+    }
 }
 
-impl ClockConfig<MclkDefined, SmclkDisabled> {
+impl<ACLK: AclkSource> ClockConfig<MclkDefined, SmclkDisabled, ACLK> {
     /// Apply clock configuration to hardware and return ACLK clock object, as SMCLK is disabled
     #[inline]
-    pub fn freeze(self, fram: &mut Fram) -> Aclk {
+    pub fn freeze(self, fram: &mut Fram) -> Aclk<ACLK> {
         let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
         self.configure_dco_fll();
         unsafe { Self::configure_fram(fram, mclk_freq) };
         self.configure_cs();
-        Aclk(self.aclk_sel.freq())
+        Aclk(ACLK::FREQ, PhantomData)
+    }
+
+    /// Apply clock configuration to hardware and return ACLK clock object, as SMCLK is disabled, as
+    /// well as a cycle-counted delay provider based on the MCLK frequency
+    #[inline]
+    pub fn freeze_with_delay(self, fram: &mut Fram) -> (Aclk<ACLK>, Delay) {
+        let mclk_freq = self.mclk.0.freq() >> (self.mclk_div as u32);
+        (self.freeze(fram), Delay::new(mclk_freq))
     }
 }
 
 /// SMCLK clock object
-pub struct Smclk(u32);
-/// ACLK clock object
-pub struct Aclk(u16);
+pub struct Smclk(pub(crate) u32);
+/// ACLK clock object, typed by its source
+pub struct Aclk<SRC: AclkSource = Refo>(pub(crate) u16, PhantomData<SRC>);
 
 /// Trait for configured clock objects
 pub trait Clock {
@@ -380,7 +357,7 @@ impl Clock for Smclk {
     }
 }
 
-impl Clock for Aclk {
+impl<SRC: AclkSource> Clock for Aclk<SRC> {
     type Freq = u16;
 
     #[inline]
@@ -0,0 +1,181 @@
+//! Blocking delays
+//!
+//! `Delay` busy-waits by counting CPU cycles, using the MCLK frequency captured when the clocks
+//! were frozen. It requires no peripherals, but its accuracy depends on the MCLK frequency being
+//! exact and the CPU not being interrupted. Timers can also be used as delay providers, in which
+//! case the delay length is determined by the timer clock instead.
+
+use crate::timer::{Timer, TimerPeriph};
+use core::arch::asm;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+
+// Each iteration of the busy loop takes 3 cycles: 1 for the decrement and 2 for the jump
+const CYCLES_PER_LOOP: u32 = 3;
+
+/// Cycle-counted delay provider, returned by `ClockConfig::freeze_with_delay`
+#[derive(Clone, Copy)]
+pub struct Delay {
+    loops_per_ms: u16,
+}
+
+impl Delay {
+    // MCLK can't exceed 24 MHz, so the loop count per millisecond always fits in 16 bits
+    #[inline]
+    pub(crate) fn new(mclk_freq: u32) -> Self {
+        Delay {
+            loops_per_ms: (mclk_freq / 1000 / CYCLES_PER_LOOP).max(1) as u16,
+        }
+    }
+
+    #[inline(always)]
+    fn spin(loops: u16) {
+        if loops == 0 {
+            return;
+        }
+        unsafe {
+            asm!(
+                "1:",
+                "dec {0}",
+                "jnz 1b",
+                inout(reg) loops => _,
+                options(nomem, nostack)
+            )
+        };
+    }
+
+    /// Block for the specified number of milliseconds
+    #[inline]
+    pub fn delay_ms(&mut self, ms: u32) {
+        for _ in 0..ms {
+            Self::spin(self.loops_per_ms);
+        }
+    }
+
+    /// Block for the specified number of microseconds
+    #[inline]
+    pub fn delay_us(&mut self, us: u32) {
+        self.delay_ms(us / 1000);
+        let loops = ((us % 1000) * self.loops_per_ms as u32).div_ceil(1000);
+        Self::spin(loops as u16);
+    }
+}
+
+impl DelayMs<u32> for Delay {
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        Delay::delay_ms(self, ms)
+    }
+}
+
+impl DelayUs<u32> for Delay {
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        Delay::delay_us(self, us)
+    }
+}
+
+// Tick counts of long delays with fast timer clocks can exceed 32 bits
+#[inline]
+fn timer_delay<T: TimerPeriph>(timer: &mut Timer<T>, time: u32, units_per_sec: u32) {
+    let mut ticks = (time as u64 * timer.tick_freq() as u64).div_ceil(units_per_sec as u64);
+    while ticks > 0 {
+        let chunk = ticks.min(u32::MAX as u64);
+        timer.delay_ticks(chunk as u32);
+        ticks -= chunk;
+    }
+}
+
+impl<T: TimerPeriph> DelayMs<u32> for Timer<T> {
+    /// Blocks using the timer as a countdown. Any previously started countdown is cancelled.
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        timer_delay(self, ms, 1000);
+    }
+}
+
+impl<T: TimerPeriph> DelayUs<u32> for Timer<T> {
+    /// Blocks using the timer as a countdown. Any previously started countdown is cancelled.
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        timer_delay(self, us, 1_000_000);
+    }
+}
+
+impl DelayMs<u16> for Delay {
+    #[inline]
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl DelayMs<u8> for Delay {
+    #[inline]
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl DelayUs<u16> for Delay {
+    #[inline]
+    fn delay_us(&mut self, us: u16) {
+        DelayUs::<u32>::delay_us(self, us as u32)
+    }
+}
+
+impl DelayUs<u8> for Delay {
+    #[inline]
+    fn delay_us(&mut self, us: u8) {
+        DelayUs::<u32>::delay_us(self, us as u32)
+    }
+}
+
+impl<T: TimerPeriph> DelayMs<u16> for Timer<T> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl<T: TimerPeriph> DelayMs<u8> for Timer<T> {
+    #[inline]
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl<T: TimerPeriph> DelayUs<u16> for Timer<T> {
+    #[inline]
+    fn delay_us(&mut self, us: u16) {
+        DelayUs::<u32>::delay_us(self, us as u32)
+    }
+}
+
+impl<T: TimerPeriph> DelayUs<u8> for Timer<T> {
+    #[inline]
+    fn delay_us(&mut self, us: u8) {
+        DelayUs::<u32>::delay_us(self, us as u32)
+    }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use embedded_hal_1::delay::DelayNs;
+
+    impl DelayNs for Delay {
+        #[inline]
+        fn delay_ns(&mut self, ns: u32) {
+            Delay::delay_us(self, ns.div_ceil(1000));
+        }
+
+        #[inline]
+        fn delay_us(&mut self, us: u32) {
+            Delay::delay_us(self, us);
+        }
+
+        #[inline]
+        fn delay_ms(&mut self, ms: u32) {
+            Delay::delay_ms(self, ms);
+        }
+    }
+}
@@ -7,9 +7,15 @@
 //! Note that interrupts are only supported by the hardware on ports **1 to 4**, so interrupt-related
 //! methods are only available on those pins.
 //!
+//! Pins can be converted into `PortPin`s or `ErasedPin`s, which store the pin number, or the port
+//! and pin number, at runtime instead of in the type. Several pins of the same port can be grouped
+//! into a `PortBus` to read or write them in parallel.
+//!
 //! Pins can be converted to alternate functionalities 1 to 3, but the availability of these
 //! conversions on each pin is limited by the hardware capabilities in the [`datasheet`], so not
 //! every pin in every configuration can be converted to every alternate functionality.
+//! Conversions named after peripheral signals, such as `into_uca0_txd` or `into_tb0_ccr3`, also
+//! set the pin direction expected by the corresponding HAL peripheral.
 //!
 //! [`datasheet`]: http://www.ti.com/lit/ds/symlink/msp430fr2475.pdf
 
@@ -21,6 +27,11 @@ use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, Toggleab
 use msp430fr247x as pac;
 pub use pac::{P1, P2, P3, P4, P5, P6};
 
+mod bus;
+mod erased;
+pub use bus::{BusPins, PortBus};
+pub use erased::{ErasedPin, NoInterrupts, PortId, PortPin};
+
 mod sealed {
     use super::*;
 
@@ -133,6 +144,10 @@ pub struct Pulldown;
 /// Pull typestate for floating inputs
 pub struct Floating;
 
+/// Direction typestate for unused pins, which are driven low as outputs so they don't float and
+/// draw current in low power modes
+pub struct Unused;
+
 /// A single GPIO pin.
 pub struct Pin<PORT: PortNum, PIN: PinNum, DIR> {
     _port: PhantomData<PORT>,
@@ -209,6 +224,27 @@ impl<PORT: IntrPortNum, PIN: PinNum, PULL> Pin<PORT, PIN, Input<PULL>> {
         self
     }
 
+    /// Trigger interrupts on both edges, by flipping the edge after every interrupt handled with
+    /// `PxIV::get_interrupt_vector_edge`. Starts with the edge opposite to the current input
+    /// level, and clears the interrupt flag.
+    #[inline]
+    pub fn select_both_edges_trigger(&mut self, pxiv: &mut PxIV<PORT>) -> &mut Self {
+        let p = unsafe { PORT::steal() };
+        let high = p.pxin_rd().check(PIN::NUM) != 0;
+        if high {
+            p.pxies_set(PIN::SET_MASK);
+        } else {
+            p.pxies_clear(PIN::CLR_MASK);
+        }
+        p.pxifg_clear(PIN::CLR_MASK);
+        // The level changed while the edge was being selected
+        if (p.pxin_rd().check(PIN::NUM) != 0) != high {
+            p.pxifg_set(PIN::SET_MASK);
+        }
+        pxiv.both_edges |= PIN::SET_MASK;
+        self
+    }
+
     /// Enable interrupts on input pin.
     /// Note that changing other GPIO configurations while interrupts are enabled can cause
     /// spurious interrupts.
@@ -257,7 +293,20 @@ impl<PORT: IntrPortNum, PIN: PinNum, PULL> Pin<PORT, PIN, Input<PULL>> {
 }
 
 /// Interrupt vector register used to determine which pin caused a port ISR
-pub struct PxIV<PORT: PortNum>(PhantomData<PORT>);
+pub struct PxIV<PORT: PortNum> {
+    // Pins whose interrupt edge is flipped after every interrupt
+    both_edges: u8,
+    _port: PhantomData<PORT>,
+}
+
+/// Direction of the edge that triggered a pin interrupt
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    /// Low to high transition
+    Rising,
+    /// High to low transition
+    Falling,
+}
 
 impl<PORT: IntrPortNum> PxIV<PORT> {
     /// When called inside an ISR, returns the pin number of the highest priority interrupt flag
@@ -279,6 +328,45 @@ impl<PORT: IntrPortNum> PxIV<PORT> {
             _ => unsafe { core::hint::unreachable_unchecked() },
         }
     }
+
+    /// Same as `get_interrupt_vector`, but also returns the direction of the edge that triggered
+    /// the interrupt. Pins in both-edges mode are rearmed for the opposite edge. The edge is
+    /// meaningless for `GpioVector::NoIsr`.
+    #[inline]
+    pub fn get_interrupt_vector_edge(&mut self) -> (GpioVector, Edge) {
+        let p = unsafe { PORT::steal() };
+        let vector = self.get_interrupt_vector();
+        let Some(num) = vector.pin_num() else {
+            return (vector, Edge::Rising);
+        };
+
+        let falling = p.pxies_rd().check(num) != 0;
+        if self.both_edges.check(num) != 0 {
+            // Arm for the opposite edge. If the pin already made that transition, possibly before
+            // PxIES changed, the flag is set in software so it's reported on the next interrupt.
+            let mask = 0u8.set(num);
+            if falling {
+                p.pxies_clear(!mask);
+            } else {
+                p.pxies_set(mask);
+            }
+            p.pxifg_clear(!mask);
+            let high = p.pxin_rd().check(num) != 0;
+            if high == falling {
+                p.pxifg_set(mask);
+            }
+        }
+
+        let edge = if falling { Edge::Falling } else { Edge::Rising };
+        (vector, edge)
+    }
+
+    /// Take a pin out of both-edges mode. Its interrupt keeps triggering on the currently
+    /// selected edge.
+    #[inline]
+    pub fn remove_both_edges<PIN: PinNum, PULL>(&mut self, _pin: &Pin<PORT, PIN, Input<PULL>>) {
+        self.both_edges &= PIN::CLR_MASK;
+    }
 }
 
 /// Indicates which pin on the GPIO port caused the ISR.
@@ -303,6 +391,24 @@ pub enum GpioVector {
     Pin7Isr,
 }
 
+impl GpioVector {
+    /// Number of the pin that caused the ISR
+    #[inline]
+    pub fn pin_num(&self) -> Option<u8> {
+        match self {
+            GpioVector::NoIsr => None,
+            GpioVector::Pin0Isr => Some(0),
+            GpioVector::Pin1Isr => Some(1),
+            GpioVector::Pin2Isr => Some(2),
+            GpioVector::Pin3Isr => Some(3),
+            GpioVector::Pin4Isr => Some(4),
+            GpioVector::Pin5Isr => Some(5),
+            GpioVector::Pin6Isr => Some(6),
+            GpioVector::Pin7Isr => Some(7),
+        }
+    }
+}
+
 impl<PORT: PortNum, PIN: PinNum, PULL> Pin<PORT, PIN, Input<PULL>> {
     /// Configures pin as output
     #[inline]
@@ -313,6 +419,20 @@ impl<PORT: PortNum, PIN: PinNum, PULL> Pin<PORT, PIN, Input<PULL>> {
     }
 }
 
+impl<PORT: PortNum, PIN: PinNum> Pin<PORT, PIN, Unused> {
+    /// Put the unused pin to use as an output
+    #[inline]
+    pub fn to_output(self) -> Pin<PORT, PIN, Output> {
+        make_pin!()
+    }
+
+    /// Put the unused pin to use as a floating input
+    #[inline]
+    pub fn to_input_floating(self) -> Pin<PORT, PIN, Input<Floating>> {
+        make_pin!(Output).to_input_floating()
+    }
+}
+
 impl<PORT: PortNum, PIN: PinNum> Pin<PORT, PIN, Output> {
     /// Configures pin as floating input
     #[inline]
@@ -396,6 +516,66 @@ impl<PORT: PortNum, PIN: PinNum> ToggleableOutputPin for Pin<PORT, PIN, Output>
     }
 }
 
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_1::digital::{self, ErrorType};
+
+    impl<PORT: PortNum, PIN: PinNum, DIR> ErrorType for Pin<PORT, PIN, DIR> {
+        type Error = Infallible;
+    }
+
+    impl<PORT: PortNum, PIN: PinNum, PULL> digital::InputPin for Pin<PORT, PIN, Input<PULL>> {
+        #[inline]
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            let p = unsafe { PORT::steal() };
+            Ok(p.pxin_rd().check(PIN::NUM) != 0)
+        }
+
+        #[inline]
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_high().map(|r| !r)
+        }
+    }
+
+    impl<PORT: PortNum, PIN: PinNum> digital::OutputPin for Pin<PORT, PIN, Output> {
+        #[inline]
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            let p = unsafe { PORT::steal() };
+            p.pxout_clear(PIN::CLR_MASK);
+            Ok(())
+        }
+
+        #[inline]
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            let p = unsafe { PORT::steal() };
+            p.pxout_set(PIN::SET_MASK);
+            Ok(())
+        }
+    }
+
+    impl<PORT: PortNum, PIN: PinNum> digital::StatefulOutputPin for Pin<PORT, PIN, Output> {
+        #[inline]
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            let p = unsafe { PORT::steal() };
+            Ok(p.pxout_rd().check(PIN::NUM) != 0)
+        }
+
+        #[inline]
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_set_high().map(|r| !r)
+        }
+
+        #[inline]
+        fn toggle(&mut self) -> Result<(), Self::Error> {
+            let p = unsafe { PORT::steal() };
+            p.pxout_toggle(PIN::SET_MASK);
+            Ok(())
+        }
+    }
+}
+
 /// GPIO parts for a specific port, including all 8 pins.
 pub struct Parts<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
     /// Pin0
@@ -421,7 +601,8 @@ pub struct Parts<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
 impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
     Parts<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
 {
-    /// Converts all parts into a GPIO batch so the entire port can be configured at once
+    /// Converts all parts into a GPIO batch so the entire port can be reconfigured at once. The
+    /// batch starts out with the current pin typestates and interrupt edges.
     #[inline]
     pub fn batch(self) -> Batch<PORT, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7> {
         Batch::create()
@@ -438,7 +619,10 @@ impl<PORT: PortNum, DIR0, DIR1, DIR2, DIR3, DIR4, DIR5, DIR6, DIR7>
             pin5: make_pin!(),
             pin6: make_pin!(),
             pin7: make_pin!(),
-            pxiv: PxIV(PhantomData),
+            pxiv: PxIV {
+                both_edges: 0,
+                _port: PhantomData,
+            },
         }
     }
 }
@@ -655,7 +839,6 @@ impl<DIR> ToAlternate2 for Pin<P1, Pin4, DIR> {}
 impl<DIR> ToAlternate2 for Pin<P1, Pin5, DIR> {}
 impl<PULL> ToAlternate2 for Pin<P1, Pin6, Input<PULL>> {}
 impl<DIR> ToAlternate2 for Pin<P1, Pin7, DIR> {}
-impl ToAlternate2 for Pin<P1, Pin7, Output> {}
 // P1 alternate 3
 impl<PIN: PinNum, DIR> ToAlternate3 for Pin<P1, PIN, DIR> {}
 
@@ -736,3 +919,133 @@ impl<DIR> ToAlternate1 for Pin<P6, Pin2, DIR> {}
 // P6 alternate 3
 impl<DIR> ToAlternate3 for Pin<P6, Pin0, DIR> {}
 
+// Set the direction of a GPIO pin before converting it to a peripheral function
+impl<PORT: PortNum, PIN: PinNum, DIR: GpioFunction> Pin<PORT, PIN, DIR> {
+    #[inline(always)]
+    fn output_dir(self) -> Pin<PORT, PIN, Output> {
+        let p = unsafe { PORT::steal() };
+        p.pxdir_set(PIN::SET_MASK);
+        make_pin!()
+    }
+
+    #[inline(always)]
+    fn floating_dir(self) -> Pin<PORT, PIN, Input<Floating>> {
+        let p = unsafe { PORT::steal() };
+        p.pxdir_clear(PIN::CLR_MASK);
+        p.pxren_clear(PIN::CLR_MASK);
+        make_pin!()
+    }
+}
+
+// Peripheral-named conversions, which select both the alternate function and the direction the
+// HAL peripheral modules expect. They go through the alternate function map above, so a
+// conversion that doesn't match the map fails to compile.
+macro_rules! pin_function {
+    ($(
+        $(#[$attr:meta])*
+        $Px:ident $Pinx:ident: $name:ident => $dir:ident.$to_alt:ident() -> $Alt:ident<$Dir:ty>;
+    )*) => {
+        $(
+            impl<DIR: GpioFunction> Pin<$Px, $Pinx, DIR> {
+                $(#[$attr])*
+                #[inline]
+                pub fn $name(self) -> Pin<$Px, $Pinx, $Alt<$Dir>> {
+                    self.$dir().$to_alt()
+                }
+            }
+        )*
+    };
+}
+
+pin_function! {
+    /// Convert to ADC input A0
+    P1 Pin0: into_adc_a0 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A1
+    P1 Pin1: into_adc_a1 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A2
+    P1 Pin2: into_adc_a2 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A3
+    P1 Pin3: into_adc_a3 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A4
+    P1 Pin4: into_adc_a4 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A5
+    P1 Pin5: into_adc_a5 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A6
+    P1 Pin6: into_adc_a6 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+    /// Convert to ADC input A7
+    P1 Pin7: into_adc_a7 => floating_dir.to_alternate3() -> Alternate3<Input<Floating>>;
+
+    /// Convert to eUSCI_A0 UART transmit data
+    P1 Pin4: into_uca0_txd => output_dir.to_alternate1() -> Alternate1<Output>;
+    /// Convert to eUSCI_A0 UART receive data
+    P1 Pin5: into_uca0_rxd => floating_dir.to_alternate1() -> Alternate1<Input<Floating>>;
+    /// Convert to eUSCI_A0 clock
+    P1 Pin6: into_uca0_clk => floating_dir.to_alternate1() -> Alternate1<Input<Floating>>;
+    /// Convert to eUSCI_A1 UART transmit data
+    P2 Pin6: into_uca1_txd => output_dir.to_alternate1() -> Alternate1<Output>;
+    /// Convert to eUSCI_A1 UART receive data
+    P2 Pin5: into_uca1_rxd => floating_dir.to_alternate1() -> Alternate1<Input<Floating>>;
+    /// Convert to eUSCI_A1 clock
+    P2 Pin4: into_uca1_clk => floating_dir.to_alternate1() -> Alternate1<Input<Floating>>;
+
+    /// Convert to TB0 external clock input
+    P6 Pin2: into_tb0_clk => floating_dir.to_alternate1() -> Alternate1<Input<Floating>>;
+    /// Convert to TB0 CCR1 compare output
+    P4 Pin7: into_tb0_ccr1 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR2 compare output
+    P5 Pin0: into_tb0_ccr2 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR3 compare output
+    P5 Pin1: into_tb0_ccr3 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR4 compare output
+    P5 Pin2: into_tb0_ccr4 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR5 compare output
+    P4 Pin3: into_tb0_ccr5 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR6 compare output
+    P4 Pin4: into_tb0_ccr6 => output_dir.to_alternate2() -> Alternate2<Output>;
+    /// Convert to TB0 CCR1 capture input A
+    P4 Pin7: into_tb0_cci1a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+    /// Convert to TB0 CCR2 capture input A
+    P5 Pin0: into_tb0_cci2a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+    /// Convert to TB0 CCR3 capture input A
+    P5 Pin1: into_tb0_cci3a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+    /// Convert to TB0 CCR4 capture input A
+    P5 Pin2: into_tb0_cci4a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+    /// Convert to TB0 CCR5 capture input A
+    P5 Pin3: into_tb0_cci5a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+    /// Convert to TB0 CCR6 capture input A
+    P5 Pin4: into_tb0_cci6a => floating_dir.to_alternate2() -> Alternate2<Input<Floating>>;
+}
+
+// The named conversions must produce exactly the pin types the peripheral modules take, so the
+// two pin tables can't drift apart
+mod peripheral_pins {
+    use super::*;
+    use crate::capture::CapturePeriph;
+    use crate::pwm::PwmPeriph;
+    use crate::serial::*;
+    use crate::timer::*;
+    use pac::TB0;
+
+    type Check<PORT, PIN, T> = fn(Pin<PORT, PIN, Input<Floating>>) -> T;
+
+    const _: Check<P1, Pin4, UsciA0TxPin> = |p| p.into_uca0_txd().into();
+    const _: Check<P1, Pin5, UsciA0RxPin> = |p| p.into_uca0_rxd().into();
+    const _: Check<P1, Pin6, UsciA0ClockPin> = |p| p.into_uca0_clk().into();
+    const _: Check<P2, Pin6, UsciA1TxPin> = |p| p.into_uca1_txd().into();
+    const _: Check<P2, Pin5, UsciA1RxPin> = |p| p.into_uca1_rxd().into();
+    const _: Check<P2, Pin4, UsciA1ClockPin> = |p| p.into_uca1_clk().into();
+
+    const _: Check<P6, Pin2, <TB0 as TimerPeriph>::Tbxclk> = |p| p.into_tb0_clk();
+    const _: Check<P4, Pin7, <TB0 as PwmPeriph<CCR1>>::Gpio> = |p| p.into_tb0_ccr1();
+    const _: Check<P5, Pin0, <TB0 as PwmPeriph<CCR2>>::Gpio> = |p| p.into_tb0_ccr2();
+    const _: Check<P5, Pin1, <TB0 as PwmPeriph<CCR3>>::Gpio> = |p| p.into_tb0_ccr3();
+    const _: Check<P5, Pin2, <TB0 as PwmPeriph<CCR4>>::Gpio> = |p| p.into_tb0_ccr4();
+    const _: Check<P4, Pin3, <TB0 as PwmPeriph<CCR5>>::Gpio> = |p| p.into_tb0_ccr5();
+    const _: Check<P4, Pin4, <TB0 as PwmPeriph<CCR6>>::Gpio> = |p| p.into_tb0_ccr6();
+    const _: Check<P4, Pin7, <TB0 as CapturePeriph>::Gpio1> = |p| p.into_tb0_cci1a();
+    const _: Check<P5, Pin0, <TB0 as CapturePeriph>::Gpio2> = |p| p.into_tb0_cci2a();
+    const _: Check<P5, Pin1, <TB0 as CapturePeriph>::Gpio3> = |p| p.into_tb0_cci3a();
+    const _: Check<P5, Pin2, <TB0 as CapturePeriph>::Gpio4> = |p| p.into_tb0_cci4a();
+    const _: Check<P5, Pin3, <TB0 as CapturePeriph>::Gpio5> = |p| p.into_tb0_cci5a();
+    const _: Check<P5, Pin4, <TB0 as CapturePeriph>::Gpio6> = |p| p.into_tb0_cci6a();
+}
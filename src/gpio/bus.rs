@@ -0,0 +1,122 @@
+//! Parallel access to several pins of a port
+//!
+//! `PortBus` groups pins of the same port and direction, and reads or writes all of them with a
+//! single register access. The pins are passed in as a tuple, and the bus mask is derived from
+//! their pin numbers at compile time. Values are given in port bit positions, so bit `n` of a
+//! value corresponds to pin `n` of the port, and bits outside the mask are ignored.
+
+use super::{Input, Output, Pin, PinNum, PortNum};
+use core::marker::PhantomData;
+
+/// Tuple of pins of the same port and direction that can form a `PortBus`
+pub trait BusPins<PORT: PortNum, DIR> {
+    /// Mask of the pins in the tuple
+    const MASK: u8;
+}
+
+macro_rules! bus_pins {
+    ($($PIN:ident),+) => {
+        impl<PORT: PortNum, DIR, $($PIN: PinNum),+> BusPins<PORT, DIR> for ($(Pin<PORT, $PIN, DIR>,)+) {
+            const MASK: u8 = {
+                let mask = 0 $(| $PIN::SET_MASK)+;
+                let count: u32 = 0 $(+ { let _ = $PIN::NUM; 1 })+;
+                // Each pin can only be part of the bus once
+                assert!(mask.count_ones() == count, "bus pins must be distinct");
+                mask
+            };
+        }
+    };
+}
+
+bus_pins!(A);
+bus_pins!(A, B);
+bus_pins!(A, B, C);
+bus_pins!(A, B, C, D);
+bus_pins!(A, B, C, D, E);
+bus_pins!(A, B, C, D, E, F);
+bus_pins!(A, B, C, D, E, F, G);
+bus_pins!(A, B, C, D, E, F, G, H);
+
+/// Group of pins of the same port that are accessed in parallel
+pub struct PortBus<PORT: PortNum, DIR, PINS: BusPins<PORT, DIR>> {
+    pins: PINS,
+    _port: PhantomData<PORT>,
+    _dir: PhantomData<DIR>,
+}
+
+impl<PORT: PortNum, DIR, PINS: BusPins<PORT, DIR>> PortBus<PORT, DIR, PINS> {
+    /// Mask of the pins on the bus
+    pub const MASK: u8 = PINS::MASK;
+
+    /// Group a tuple of pins into a bus
+    #[inline]
+    pub fn new(pins: PINS) -> Self {
+        // Force evaluation of the mask, so duplicate pins are rejected at compile time
+        let _ = Self::MASK;
+        PortBus {
+            pins,
+            _port: PhantomData,
+            _dir: PhantomData,
+        }
+    }
+
+    /// Release the pins
+    #[inline]
+    pub fn free(self) -> PINS {
+        self.pins
+    }
+}
+
+impl<PORT: PortNum, PULL, PINS: BusPins<PORT, Input<PULL>>> PortBus<PORT, Input<PULL>, PINS> {
+    /// Read the input levels of all pins on the bus
+    #[inline]
+    pub fn read(&self) -> u8 {
+        let p = unsafe { PORT::steal() };
+        p.pxin_rd() & Self::MASK
+    }
+}
+
+impl<PORT: PortNum, PINS: BusPins<PORT, Output>> PortBus<PORT, Output, PINS> {
+    /// Write the output levels of all pins on the bus at once. Pins on the port outside the bus
+    /// keep their levels. Unless the bus spans the whole port, the write is a read-modify-write
+    /// of PxOUT, done inside a critical section.
+    #[inline]
+    pub fn write(&mut self, value: u8) {
+        let p = unsafe { PORT::steal() };
+        if Self::MASK == 0xFF {
+            p.pxout_wr(value);
+        } else {
+            critical_section::with(|_| {
+                p.pxout_wr((p.pxout_rd() & !Self::MASK) | (value & Self::MASK))
+            });
+        }
+    }
+
+    /// Read back the output levels of all pins on the bus
+    #[inline]
+    pub fn read_output(&self) -> u8 {
+        let p = unsafe { PORT::steal() };
+        p.pxout_rd() & Self::MASK
+    }
+
+    /// Drive the selected pins high in a single instruction
+    #[inline]
+    pub fn set_bits(&mut self, bits: u8) {
+        let p = unsafe { PORT::steal() };
+        p.pxout_set(bits & Self::MASK);
+    }
+
+    /// Drive the selected pins low in a single instruction
+    #[inline]
+    pub fn clear_bits(&mut self, bits: u8) {
+        let p = unsafe { PORT::steal() };
+        p.pxout_clear(!(bits & Self::MASK));
+    }
+
+    /// Toggle the selected pins in a single instruction
+    #[inline]
+    pub fn toggle_bits(&mut self, bits: u8) {
+        let p = unsafe { PORT::steal() };
+        p.pxout_toggle(bits & Self::MASK);
+    }
+}
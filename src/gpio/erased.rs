@@ -0,0 +1,528 @@
+//! Type-erased GPIO pins
+//!
+//! `PortPin` keeps the port in the type but stores the pin number at runtime, and `ErasedPin`
+//! stores both the port and the pin number at runtime, so pins of different ports and numbers can
+//! be kept in arrays or passed to drivers without generics. Both only keep the direction in the
+//! type, and implement the same digital traits as `Pin`.
+//!
+//! Interrupt configuration is available on `PortPin`s of ports 1 to 4. Since the port of an
+//! `ErasedPin` is only known at runtime, its interrupt methods return `NoInterrupts` for pins on
+//! ports without interrupt support.
+
+use super::{Input, IntrPortNum, Output, Pin, PinNum, PortNum, P1, P2, P3, P4, P5, P6};
+use crate::hw_traits::gpio::{GpioPeriph, IntrPeriph};
+use crate::hw_traits::Steal;
+use core::marker::PhantomData;
+use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+
+pub use crate::hw_traits::gpio::PortId;
+
+/// Error returned when configuring interrupts on a pin whose port doesn't support them
+#[derive(Debug)]
+pub struct NoInterrupts;
+
+/// GPIO pin with the pin number erased from the type
+pub struct PortPin<PORT: PortNum, DIR> {
+    num: u8,
+    _port: PhantomData<PORT>,
+    _dir: PhantomData<DIR>,
+}
+
+/// GPIO pin with the port and pin number erased from the type
+pub struct ErasedPin<DIR> {
+    port: PortId,
+    num: u8,
+    _dir: PhantomData<DIR>,
+}
+
+impl<PORT: PortNum, PIN: PinNum, DIR> Pin<PORT, PIN, DIR> {
+    /// Erase the pin number from the type
+    #[inline]
+    pub fn erase_number(self) -> PortPin<PORT, DIR> {
+        PortPin {
+            num: PIN::NUM,
+            _port: PhantomData,
+            _dir: PhantomData,
+        }
+    }
+
+    /// Erase the port and pin number from the type
+    #[inline]
+    pub fn degrade(self) -> ErasedPin<DIR> {
+        self.erase_number().degrade()
+    }
+}
+
+impl<PORT: PortNum, DIR> PortPin<PORT, DIR> {
+    /// Erase the port from the type
+    #[inline]
+    pub fn degrade(self) -> ErasedPin<DIR> {
+        ErasedPin {
+            port: PORT::ID,
+            num: self.num,
+            _dir: PhantomData,
+        }
+    }
+
+    /// Pin number within the port
+    #[inline]
+    pub fn pin_num(&self) -> u8 {
+        self.num
+    }
+}
+
+impl<DIR> ErasedPin<DIR> {
+    /// Port of the pin
+    #[inline]
+    pub fn port(&self) -> PortId {
+        self.port
+    }
+
+    /// Pin number within the port
+    #[inline]
+    pub fn pin_num(&self) -> u8 {
+        self.num
+    }
+
+    /// Whether the port of the pin supports interrupts
+    #[inline]
+    pub fn supports_interrupts(&self) -> bool {
+        matches!(self.port, PortId::P1 | PortId::P2 | PortId::P3 | PortId::P4)
+    }
+}
+
+// Run `$body` with `$p` bound to the port peripheral of an erased pin
+macro_rules! with_port {
+    ($port:expr, |$p:ident| $body:expr) => {
+        match $port {
+            PortId::P1 => with_port!(@steal P1, $p, $body),
+            PortId::P2 => with_port!(@steal P2, $p, $body),
+            PortId::P3 => with_port!(@steal P3, $p, $body),
+            PortId::P4 => with_port!(@steal P4, $p, $body),
+            PortId::P5 => with_port!(@steal P5, $p, $body),
+            PortId::P6 => with_port!(@steal P6, $p, $body),
+        }
+    };
+
+    (@steal $Px:ident, $p:ident, $body:expr) => {{
+        let $p = unsafe { $Px::steal() };
+        $body
+    }};
+}
+
+// Same as `with_port`, but returns `NoInterrupts` for ports without interrupt support
+macro_rules! with_intr_port {
+    ($port:expr, |$p:ident| $body:expr) => {
+        match $port {
+            PortId::P1 => with_port!(@steal P1, $p, $body),
+            PortId::P2 => with_port!(@steal P2, $p, $body),
+            PortId::P3 => with_port!(@steal P3, $p, $body),
+            PortId::P4 => with_port!(@steal P4, $p, $body),
+            PortId::P5 | PortId::P6 => return Err(NoInterrupts.into()),
+        }
+    };
+}
+
+// Register accesses shared by both kinds of erased pins
+trait RawPin {
+    fn mask(&self) -> u8;
+    fn in_rd(&self) -> u8;
+    fn out_rd(&self) -> u8;
+    fn out_set(&self);
+    fn out_clear(&self);
+    fn out_toggle(&self);
+}
+
+impl<PORT: PortNum, DIR> RawPin for PortPin<PORT, DIR> {
+    #[inline(always)]
+    fn mask(&self) -> u8 {
+        1 << self.num
+    }
+
+    #[inline(always)]
+    fn in_rd(&self) -> u8 {
+        unsafe { PORT::steal() }.pxin_rd()
+    }
+
+    #[inline(always)]
+    fn out_rd(&self) -> u8 {
+        unsafe { PORT::steal() }.pxout_rd()
+    }
+
+    #[inline(always)]
+    fn out_set(&self) {
+        unsafe { PORT::steal() }.pxout_set(self.mask());
+    }
+
+    #[inline(always)]
+    fn out_clear(&self) {
+        unsafe { PORT::steal() }.pxout_clear(!self.mask());
+    }
+
+    #[inline(always)]
+    fn out_toggle(&self) {
+        unsafe { PORT::steal() }.pxout_toggle(self.mask());
+    }
+}
+
+impl<DIR> RawPin for ErasedPin<DIR> {
+    #[inline(always)]
+    fn mask(&self) -> u8 {
+        1 << self.num
+    }
+
+    #[inline(always)]
+    fn in_rd(&self) -> u8 {
+        with_port!(self.port, |p| p.pxin_rd())
+    }
+
+    #[inline(always)]
+    fn out_rd(&self) -> u8 {
+        with_port!(self.port, |p| p.pxout_rd())
+    }
+
+    #[inline(always)]
+    fn out_set(&self) {
+        with_port!(self.port, |p| p.pxout_set(self.mask()))
+    }
+
+    #[inline(always)]
+    fn out_clear(&self) {
+        with_port!(self.port, |p| p.pxout_clear(!self.mask()))
+    }
+
+    #[inline(always)]
+    fn out_toggle(&self) {
+        with_port!(self.port, |p| p.pxout_toggle(self.mask()))
+    }
+}
+
+impl<PORT: IntrPortNum, PULL> PortPin<PORT, Input<PULL>> {
+    /// Set interrupt trigger to rising edge and clear interrupt flag.
+    #[inline]
+    pub fn select_rising_edge_trigger(&mut self) -> &mut Self {
+        let p = unsafe { PORT::steal() };
+        p.pxies_clear(!self.mask());
+        p.pxifg_clear(!self.mask());
+        self
+    }
+
+    /// Set interrupt trigger to falling edge, the default, and clear interrupt flag.
+    #[inline]
+    pub fn select_falling_edge_trigger(&mut self) -> &mut Self {
+        let p = unsafe { PORT::steal() };
+        p.pxies_set(self.mask());
+        p.pxifg_clear(!self.mask());
+        self
+    }
+
+    /// Enable interrupts on input pin.
+    #[inline]
+    pub fn enable_interrupts(&mut self) -> &mut Self {
+        unsafe { PORT::steal() }.pxie_set(self.mask());
+        self
+    }
+
+    /// Disable interrupts on input pin.
+    #[inline]
+    pub fn disable_interrupt(&mut self) -> &mut Self {
+        unsafe { PORT::steal() }.pxie_clear(!self.mask());
+        self
+    }
+
+    /// Set interrupt flag high, triggering an ISR if interrupts are enabled.
+    #[inline]
+    pub fn set_ifg(&mut self) -> &mut Self {
+        unsafe { PORT::steal() }.pxifg_set(self.mask());
+        self
+    }
+
+    /// Clear interrupt flag.
+    #[inline]
+    pub fn clear_ifg(&mut self) -> &mut Self {
+        unsafe { PORT::steal() }.pxifg_clear(!self.mask());
+        self
+    }
+
+    /// Wait for interrupt flag to go high nonblockingly. Clear the flag if high.
+    #[inline]
+    pub fn wait_for_ifg(&mut self) -> nb::Result<(), void::Void> {
+        let p = unsafe { PORT::steal() };
+        if p.pxifg_rd() & self.mask() != 0 {
+            p.pxifg_clear(!self.mask());
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<PULL> ErasedPin<Input<PULL>> {
+    /// Set interrupt trigger to rising edge and clear interrupt flag.
+    #[inline]
+    pub fn select_rising_edge_trigger(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| {
+            p.pxies_clear(!self.mask());
+            p.pxifg_clear(!self.mask());
+        });
+        Ok(self)
+    }
+
+    /// Set interrupt trigger to falling edge, the default, and clear interrupt flag.
+    #[inline]
+    pub fn select_falling_edge_trigger(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| {
+            p.pxies_set(self.mask());
+            p.pxifg_clear(!self.mask());
+        });
+        Ok(self)
+    }
+
+    /// Enable interrupts on input pin.
+    #[inline]
+    pub fn enable_interrupts(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| p.pxie_set(self.mask()));
+        Ok(self)
+    }
+
+    /// Disable interrupts on input pin.
+    #[inline]
+    pub fn disable_interrupt(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| p.pxie_clear(!self.mask()));
+        Ok(self)
+    }
+
+    /// Set interrupt flag high, triggering an ISR if interrupts are enabled.
+    #[inline]
+    pub fn set_ifg(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| p.pxifg_set(self.mask()));
+        Ok(self)
+    }
+
+    /// Clear interrupt flag.
+    #[inline]
+    pub fn clear_ifg(&mut self) -> Result<&mut Self, NoInterrupts> {
+        with_intr_port!(self.port, |p| p.pxifg_clear(!self.mask()));
+        Ok(self)
+    }
+
+    /// Wait for interrupt flag to go high nonblockingly. Clear the flag if high.
+    #[inline]
+    pub fn wait_for_ifg(&mut self) -> nb::Result<(), NoInterrupts> {
+        with_intr_port!(self.port, |p| {
+            if p.pxifg_rd() & self.mask() != 0 {
+                p.pxifg_clear(!self.mask());
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        })
+    }
+}
+
+impl<PORT: PortNum, PULL> InputPin for PortPin<PORT, Input<PULL>> {
+    type Error = void::Void;
+
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.in_rd() & self.mask() != 0)
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|r| !r)
+    }
+}
+
+impl<PORT: PortNum> OutputPin for PortPin<PORT, Output> {
+    type Error = void::Void;
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.out_clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.out_set();
+        Ok(())
+    }
+}
+
+impl<PORT: PortNum> StatefulOutputPin for PortPin<PORT, Output> {
+    #[inline]
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.out_rd() & self.mask() != 0)
+    }
+
+    #[inline]
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        self.is_set_high().map(|r| !r)
+    }
+}
+
+impl<PORT: PortNum> ToggleableOutputPin for PortPin<PORT, Output> {
+    type Error = void::Void;
+
+    #[inline]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.out_toggle();
+        Ok(())
+    }
+}
+
+impl<PULL> InputPin for ErasedPin<Input<PULL>> {
+    type Error = void::Void;
+
+    #[inline]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.in_rd() & self.mask() != 0)
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|r| !r)
+    }
+}
+
+impl OutputPin for ErasedPin<Output> {
+    type Error = void::Void;
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.out_clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.out_set();
+        Ok(())
+    }
+}
+
+impl StatefulOutputPin for ErasedPin<Output> {
+    #[inline]
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.out_rd() & self.mask() != 0)
+    }
+
+    #[inline]
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        self.is_set_high().map(|r| !r)
+    }
+}
+
+impl ToggleableOutputPin for ErasedPin<Output> {
+    type Error = void::Void;
+
+    #[inline]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.out_toggle();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_1::digital::{self, ErrorType};
+
+    impl<PORT: PortNum, DIR> ErrorType for PortPin<PORT, DIR> {
+        type Error = Infallible;
+    }
+
+    impl<DIR> ErrorType for ErasedPin<DIR> {
+        type Error = Infallible;
+    }
+
+    impl<PORT: PortNum, PULL> digital::InputPin for PortPin<PORT, Input<PULL>> {
+        #[inline]
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.in_rd() & self.mask() != 0)
+        }
+
+        #[inline]
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_high().map(|r| !r)
+        }
+    }
+
+    impl<PULL> digital::InputPin for ErasedPin<Input<PULL>> {
+        #[inline]
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.in_rd() & self.mask() != 0)
+        }
+
+        #[inline]
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_high().map(|r| !r)
+        }
+    }
+
+    impl<PORT: PortNum> digital::OutputPin for PortPin<PORT, Output> {
+        #[inline]
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.out_clear();
+            Ok(())
+        }
+
+        #[inline]
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.out_set();
+            Ok(())
+        }
+    }
+
+    impl digital::OutputPin for ErasedPin<Output> {
+        #[inline]
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.out_clear();
+            Ok(())
+        }
+
+        #[inline]
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.out_set();
+            Ok(())
+        }
+    }
+
+    impl<PORT: PortNum> digital::StatefulOutputPin for PortPin<PORT, Output> {
+        #[inline]
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.out_rd() & self.mask() != 0)
+        }
+
+        #[inline]
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_set_high().map(|r| !r)
+        }
+
+        #[inline]
+        fn toggle(&mut self) -> Result<(), Self::Error> {
+            self.out_toggle();
+            Ok(())
+        }
+    }
+
+    impl digital::StatefulOutputPin for ErasedPin<Output> {
+        #[inline]
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.out_rd() & self.mask() != 0)
+        }
+
+        #[inline]
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            self.is_set_high().map(|r| !r)
+        }
+
+        #[inline]
+        fn toggle(&mut self) -> Result<(), Self::Error> {
+            self.out_toggle();
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,7 @@
+// Implementations of the `hw_traits` traits for the PAC peripherals. Kept apart from the trait
+// definitions so that the drivers can be built against other implementations of the traits, such
+// as the register models of the `sim` crate.
+
+mod eusci;
+mod gpio;
+mod timerb;
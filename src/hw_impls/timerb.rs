@@ -0,0 +1,277 @@
+use crate::hw_traits::timerb::*;
+use crate::hw_traits::Steal;
+use msp430fr247x as pac;
+
+macro_rules! ccrn_impl {
+    ($TBx:ident, $CCRn:ident, $tbxcctln:ident, $tbxccrn:ident) => {
+        impl CCRn<$CCRn> for pac::$TBx {
+            #[inline(always)]
+            fn set_ccrn(&self, count: u16) {
+                self.$tbxccrn.write(|w| unsafe { w.bits(count) });
+            }
+
+            #[inline(always)]
+            fn get_ccrn(&self) -> u16 {
+                self.$tbxccrn.read().bits()
+            }
+
+            #[inline(always)]
+            fn outmod_rd(&self) -> Outmod {
+                self.$tbxcctln.read().outmod().bits().into()
+            }
+
+            #[inline(always)]
+            fn config_outmod(&self, outmod: Outmod) {
+                self.$tbxcctln.modify(|r, w| {
+                    unsafe { w.bits(0) }
+                        .clld()
+                        .bits(r.clld().bits())
+                        .outmod()
+                        .bits(outmod as u8)
+                });
+            }
+
+            #[inline(always)]
+            fn config_out(&self, out: bool) {
+                self.$tbxcctln.modify(|r, w| {
+                    unsafe { w.bits(0) }
+                        .clld()
+                        .bits(r.clld().bits())
+                        .outmod()
+                        .bits(Outmod::Out as u8)
+                        .out()
+                        .bit(out)
+                });
+            }
+
+            #[inline(always)]
+            fn config_clld(&self, clld: CompareLoad) {
+                self.$tbxcctln.modify(|_, w| w.clld().bits(clld as u8));
+            }
+
+            #[inline(always)]
+            fn config_cap_mode(&self, cm: Cm, ccis: Ccis) {
+                self.$tbxcctln.write(|w| {
+                    w.cap()
+                        .capture()
+                        .scs()
+                        .sync()
+                        .cm()
+                        .bits(cm as u8)
+                        .ccis()
+                        .bits(ccis as u8)
+                });
+            }
+
+            #[inline(always)]
+            fn ccifg_rd(&self) -> bool {
+                self.$tbxcctln.read().ccifg().bit()
+            }
+
+            #[inline(always)]
+            fn cci_rd(&self) -> bool {
+                self.$tbxcctln.read().cci().bit()
+            }
+
+            #[inline(always)]
+            fn cap_sel_rd(&self) -> (Cm, Ccis) {
+                let r = self.$tbxcctln.read();
+                (r.cm().bits().into(), r.ccis().bits().into())
+            }
+
+            #[inline(always)]
+            fn config_cap_sel(&self, cm: Cm, ccis: Ccis) {
+                self.$tbxcctln
+                    .modify(|_, w| w.cm().bits(cm as u8).ccis().bits(ccis as u8));
+            }
+
+            #[inline(always)]
+            fn ccifg_clr(&self) {
+                self.$tbxcctln.modify(|_, w| w.ccifg().clear_bit());
+            }
+
+            #[inline(always)]
+            fn ccifg_set(&self) {
+                self.$tbxcctln.modify(|_, w| w.ccifg().set_bit());
+            }
+
+            #[inline(always)]
+            fn ccie_rd(&self) -> bool {
+                self.$tbxcctln.read().ccie().bit()
+            }
+
+            #[inline(always)]
+            fn ccie_set(&self) {
+                self.$tbxcctln.modify(|_, w| w.ccie().set_bit());
+            }
+
+            #[inline(always)]
+            fn ccie_clr(&self) {
+                self.$tbxcctln.modify(|_, w| w.ccie().clear_bit());
+            }
+
+            #[inline(always)]
+            fn cov_ccifg_rd(&self) -> (bool, bool) {
+                let cctl = self.$tbxcctln.read();
+                (cctl.cov().bit(), cctl.ccifg().bit())
+            }
+
+            #[inline(always)]
+            fn cov_ccifg_clr(&self) {
+                self.$tbxcctln
+                    .modify(|_, w| w.ccifg().clear_bit().cov().clear_bit());
+            }
+
+            #[inline(always)]
+            fn cov_set(&self) {
+                self.$tbxcctln.modify(|_, w| w.cov().set_bit());
+            }
+        }
+    };
+}
+
+macro_rules! timerb_impl {
+    ($TBx:ident, $tbx:ident, $tbxctl:ident, $tbxr:ident, $tbxex:ident, $tbxiv:ident, $([$CCRn:ident, $tbxcctln:ident, $tbxccrn:ident]),*) => {
+        impl Steal for pac::$TBx {
+            #[inline(always)]
+            unsafe fn steal() -> Self {
+                pac::Peripherals::steal().$TBx
+            }
+        }
+
+        impl TimerB for pac::$TBx {
+            #[inline(always)]
+            fn reset(&self) {
+                self.$tbxctl.write(|w| w.tbclr().set_bit());
+            }
+
+            #[inline(always)]
+            fn upmode(&self) {
+                self.$tbxctl.modify(|r, w| {
+                    unsafe { w.bits(r.bits()) }
+                        .tbclr()
+                        .set_bit()
+                        .tbifg()
+                        .clear_bit()
+                        .mc()
+                        .up()
+                });
+            }
+
+            #[inline(always)]
+            fn continuous(&self) {
+                self.$tbxctl.modify(|r, w| {
+                    unsafe { w.bits(r.bits()) }
+                        .tbclr()
+                        .set_bit()
+                        .tbifg()
+                        .clear_bit()
+                        .mc()
+                        .continuous()
+                });
+            }
+
+            #[inline(always)]
+            fn updown(&self) {
+                self.$tbxctl.modify(|r, w| {
+                    unsafe { w.bits(r.bits()) }
+                        .tbclr()
+                        .set_bit()
+                        .tbifg()
+                        .clear_bit()
+                        .mc()
+                        .updown()
+                });
+            }
+
+            #[inline(always)]
+            fn config_clock(
+                &self,
+                tbssel: Tbssel,
+                div: TimerDiv,
+                cntl: CounterLength,
+                clgrp: CompareGroup,
+            ) {
+                self.$tbxctl.write(|w| {
+                    w.tbssel()
+                        .bits(tbssel as u8)
+                        .id()
+                        .bits(div as u8)
+                        .cntl()
+                        .bits(cntl as u8)
+                        .tbclgrp()
+                        .bits(clgrp as u8)
+                });
+            }
+
+            #[inline(always)]
+            fn is_stopped(&self) -> bool {
+                self.$tbxctl.read().mc().is_stop()
+            }
+
+            #[inline(always)]
+            fn stop(&self) {
+                self.$tbxctl.modify(|_, w| w.mc().stop());
+            }
+
+            #[inline(always)]
+            fn set_tbidex(&self, tbidex: TimerExDiv) {
+                self.$tbxex.write(|w| w.tbidex().bits(tbidex as u8));
+            }
+
+            #[inline(always)]
+            fn set_id_reset(&self, div: TimerDiv) {
+                self.$tbxctl
+                    .modify(|_, w| w.id().bits(div as u8).tbclr().set_bit());
+            }
+
+            #[inline(always)]
+            fn tbxr_rd(&self) -> u16 {
+                self.$tbxr.read().bits()
+            }
+
+            #[inline(always)]
+            fn tbifg_rd(&self) -> bool {
+                self.$tbxctl.read().tbifg().bit()
+            }
+
+            #[inline(always)]
+            fn tbifg_clr(&self) {
+                self.$tbxctl.modify(|_, w| w.tbifg().clear_bit());
+            }
+
+            #[inline(always)]
+            fn tbie_set(&self) {
+                self.$tbxctl.modify(|_, w| w.tbie().set_bit());
+            }
+
+            #[inline(always)]
+            fn tbie_clr(&self) {
+                self.$tbxctl.modify(|_, w| w.tbie().clear_bit());
+            }
+
+            #[inline(always)]
+            fn tbxiv_rd(&self) -> u16 {
+                self.$tbxiv.read().bits()
+            }
+        }
+
+        $(ccrn_impl!($TBx, $CCRn, $tbxcctln, $tbxccrn);)*
+    };
+}
+
+timerb_impl!(
+    TB0,
+    tb0,
+    tb0ctl,
+    tb0r,
+    tb0ex0,
+    tb0iv,
+    [CCR0, tb0cctl0, tb0ccr0],
+    [CCR1, tb0cctl1, tb0ccr1],
+    [CCR2, tb0cctl2, tb0ccr2],
+    [CCR3, tb0cctl3, tb0ccr3],
+    [CCR4, tb0cctl4, tb0ccr4],
+    [CCR5, tb0cctl5, tb0ccr5],
+    [CCR6, tb0cctl6, tb0ccr6]
+);
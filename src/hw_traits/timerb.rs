@@ -1,6 +1,6 @@
 use super::Steal;
-use msp430fr247x as pac;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Tbssel {
     Tbxclk,
     Aclk,
@@ -9,6 +9,7 @@ pub enum Tbssel {
 }
 
 /// Timer clock divider
+#[derive(Clone, Copy)]
 pub enum TimerDiv {
     /// No division
     _1,
@@ -21,6 +22,7 @@ pub enum TimerDiv {
 }
 
 /// Timer expansion clock divider, applied on top of the normal clock divider
+#[derive(Clone, Copy)]
 pub enum TimerExDiv {
     /// No division
     _1,
@@ -40,6 +42,81 @@ pub enum TimerExDiv {
     _8,
 }
 
+/// Length of the timer counter
+#[derive(Clone, Copy)]
+pub enum CounterLength {
+    /// 16-bit counter
+    _16Bit,
+    /// 12-bit counter
+    _12Bit,
+    /// 10-bit counter
+    _10Bit,
+    /// 8-bit counter
+    _8Bit,
+}
+
+impl CounterLength {
+    /// Number of bits in the counter
+    #[inline]
+    pub fn bits(self) -> u8 {
+        match self {
+            CounterLength::_16Bit => 16,
+            CounterLength::_12Bit => 12,
+            CounterLength::_10Bit => 10,
+            CounterLength::_8Bit => 8,
+        }
+    }
+
+    /// Maximum value of the counter, after which it wraps to 0 in continuous mode
+    #[inline]
+    pub fn max(self) -> u16 {
+        (0xFFFF_u32 >> (16 - self.bits())) as u16
+    }
+
+    // Whether an overflow flagged after reading `count` happened before the read. Counts are read
+    // at least twice per counter period, so an overflow right after a read of the upper half is
+    // impossible.
+    #[inline]
+    pub(crate) fn overflowed_before(self, count: u16) -> bool {
+        count <= self.max() / 2
+    }
+
+    // Extend a count with a number of overflows
+    #[inline]
+    pub(crate) fn extend(self, count: u16, overflows: u32) -> u32 {
+        (overflows << self.bits()) | count as u32
+    }
+}
+
+/// Event upon which a value written to a capture-compare register is loaded into its compare latch
+/// and takes effect
+#[derive(Clone, Copy)]
+pub enum CompareLoad {
+    /// Load immediately on write
+    Immediate,
+    /// Load when the counter counts to 0
+    AtZero,
+    /// Load when the counter counts to 0 in up or continuous mode, or when it counts to either 0
+    /// or the period in up/down mode
+    AtZeroOrPeriod,
+    /// Load when the counter counts to the old compare value
+    AtCompare,
+}
+
+/// Grouping of compare latches. The latches in a group are loaded together, on the load event of
+/// the lowest-numbered register in the group.
+#[derive(Clone, Copy)]
+pub enum CompareGroup {
+    /// Each latch is loaded individually
+    Individual,
+    /// CCR1 and CCR2, CCR3 and CCR4, and CCR5 and CCR6 are grouped
+    Pairs,
+    /// CCR1 to CCR3 and CCR4 to CCR6 are grouped
+    Triples,
+    /// All latches, including CCR0, are grouped under CCR1
+    All,
+}
+
 pub enum Outmod {
     Out,
     Set,
@@ -51,6 +128,7 @@ pub enum Outmod {
     ResetSet,
 }
 
+#[derive(Clone, Copy)]
 pub enum Cm {
     NoCap,
     RisingEdge,
@@ -58,6 +136,7 @@ pub enum Cm {
     BothEdges,
 }
 
+#[derive(Clone, Copy)]
 pub enum Ccis {
     InputA,
     InputB,
@@ -65,6 +144,46 @@ pub enum Ccis {
     Vcc,
 }
 
+impl From<u8> for Outmod {
+    #[inline(always)]
+    fn from(bits: u8) -> Self {
+        match bits & 0b111 {
+            0 => Outmod::Out,
+            1 => Outmod::Set,
+            2 => Outmod::ToggleReset,
+            3 => Outmod::SetReset,
+            4 => Outmod::Toggle,
+            5 => Outmod::Reset,
+            6 => Outmod::ToggleSet,
+            _ => Outmod::ResetSet,
+        }
+    }
+}
+
+impl From<u8> for Cm {
+    #[inline(always)]
+    fn from(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Cm::NoCap,
+            1 => Cm::RisingEdge,
+            2 => Cm::FallingEdge,
+            _ => Cm::BothEdges,
+        }
+    }
+}
+
+impl From<u8> for Ccis {
+    #[inline(always)]
+    fn from(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Ccis::InputA,
+            1 => Ccis::InputB,
+            2 => Ccis::Gnd,
+            _ => Ccis::Vcc,
+        }
+    }
+}
+
 pub trait TimerB: Steal {
     /// Reset timer countdown
     fn reset(&self);
@@ -73,9 +192,11 @@ pub trait TimerB: Steal {
     fn upmode(&self);
     /// Set to continuous mode, reset timer, and clear interrupts
     fn continuous(&self);
+    /// Set to up/down mode, reset timer, and clear interrupts
+    fn updown(&self);
 
-    /// Apply clock select settings
-    fn config_clock(&self, tbssel: Tbssel, div: TimerDiv);
+    /// Apply clock select, counter length, and compare latch grouping settings
+    fn config_clock(&self, tbssel: Tbssel, div: TimerDiv, cntl: CounterLength, clgrp: CompareGroup);
 
     /// Check if timer is stopped
     fn is_stopped(&self) -> bool;
@@ -85,6 +206,11 @@ pub trait TimerB: Steal {
 
     /// Set expansion register clock divider settings
     fn set_tbidex(&self, tbidex: TimerExDiv);
+    /// Change the clock divider while running, resetting the counter as required
+    fn set_id_reset(&self, div: TimerDiv);
+
+    /// Read current counter value
+    fn tbxr_rd(&self) -> u16;
 
     fn tbifg_rd(&self) -> bool;
     fn tbifg_clr(&self);
@@ -99,17 +225,36 @@ pub trait CCRn<C>: Steal {
     fn set_ccrn(&self, count: u16);
     fn get_ccrn(&self) -> u16;
 
+    /// Read the output mode
+    fn outmod_rd(&self) -> Outmod;
+    /// Set output mode, keeping the compare latch load setting
     fn config_outmod(&self, outmod: Outmod);
+    /// Set output mode to `Out` and drive the output to a constant level, keeping the compare
+    /// latch load setting
+    fn config_out(&self, out: bool);
+    fn config_clld(&self, clld: CompareLoad);
     fn config_cap_mode(&self, cm: Cm, ccis: Ccis);
 
     fn ccifg_rd(&self) -> bool;
     fn ccifg_clr(&self);
+    /// Set the interrupt flag in software, making the interrupt pending
+    fn ccifg_set(&self);
 
+    /// Read the current level of the capture input
+    fn cci_rd(&self) -> bool;
+    /// Read the capture mode and capture input select
+    fn cap_sel_rd(&self) -> (Cm, Ccis);
+    /// Set the capture mode and capture input select, keeping the rest of the capture settings
+    fn config_cap_sel(&self, cm: Cm, ccis: Ccis);
+
+    fn ccie_rd(&self) -> bool;
     fn ccie_set(&self);
     fn ccie_clr(&self);
 
     fn cov_ccifg_rd(&self) -> (bool, bool);
     fn cov_ccifg_clr(&self);
+    /// Set the capture overflow flag in software
+    fn cov_set(&self);
 }
 
 /// Label for capture-compare register 0
@@ -126,177 +271,3 @@ pub struct CCR4;
 pub struct CCR5;
 /// Label for capture-compare register 6
 pub struct CCR6;
-
-macro_rules! ccrn_impl {
-    ($TBx:ident, $CCRn:ident, $tbxcctln:ident, $tbxccrn:ident) => {
-        impl CCRn<$CCRn> for pac::$TBx {
-            #[inline(always)]
-            fn set_ccrn(&self, count: u16) {
-                self.$tbxccrn.write(|w| unsafe { w.bits(count) });
-            }
-
-            #[inline(always)]
-            fn get_ccrn(&self) -> u16 {
-                self.$tbxccrn.read().bits()
-            }
-
-            #[inline(always)]
-            fn config_outmod(&self, outmod: Outmod) {
-                self.$tbxcctln.write(|w| w.outmod().bits(outmod as u8));
-            }
-
-            #[inline(always)]
-            fn config_cap_mode(&self, cm: Cm, ccis: Ccis) {
-                self.$tbxcctln.write(|w| {
-                    w.cap()
-                        .capture()
-                        .scs()
-                        .sync()
-                        .cm()
-                        .bits(cm as u8)
-                        .ccis()
-                        .bits(ccis as u8)
-                });
-            }
-
-            #[inline(always)]
-            fn ccifg_rd(&self) -> bool {
-                self.$tbxcctln.read().ccifg().bit()
-            }
-
-            #[inline(always)]
-            fn ccifg_clr(&self) {
-                self.$tbxcctln.write(|w| w.ccifg().clear_bit());
-            }
-
-            #[inline(always)]
-            fn ccie_set(&self) {
-                self.$tbxcctln.write(|w| w.ccie().set_bit());
-            }
-
-            #[inline(always)]
-            fn ccie_clr(&self) {
-                self.$tbxcctln.write(|w| w.ccie().clear_bit());
-            }
-
-            #[inline(always)]
-            fn cov_ccifg_rd(&self) -> (bool, bool) {
-                let cctl = self.$tbxcctln.read();
-                (cctl.cov().bit(), cctl.ccifg().bit())
-            }
-
-            #[inline(always)]
-            fn cov_ccifg_clr(&self) {
-                    self.$tbxcctln
-                        .write(|w| w.ccifg().clear_bit().cov().clear_bit());
-            }
-        }
-    };
-}
-
-macro_rules! timerb_impl {
-    ($TBx:ident, $tbx:ident, $tbxctl:ident, $tbxex:ident, $tbxiv:ident, $([$CCRn:ident, $tbxcctln:ident, $tbxccrn:ident]),*) => {
-        impl Steal for pac::$TBx {
-            #[inline(always)]
-            unsafe fn steal() -> Self {
-                pac::Peripherals::steal().$TBx
-            }
-        }
-
-        impl TimerB for pac::$TBx {
-            #[inline(always)]
-            fn reset(&self) {
-                self.$tbxctl.write(|w| w.tbclr().set_bit());
-            }
-
-            #[inline(always)]
-            fn upmode(&self) {
-                self.$tbxctl.modify(|r, w| {
-                    unsafe { w.bits(r.bits()) }
-                        .tbclr()
-                        .set_bit()
-                        .tbifg()
-                        .clear_bit()
-                        .mc()
-                        .up()
-                });
-            }
-
-            #[inline(always)]
-            fn continuous(&self) {
-                self.$tbxctl.modify(|r, w| {
-                    unsafe { w.bits(r.bits()) }
-                        .tbclr()
-                        .set_bit()
-                        .tbifg()
-                        .clear_bit()
-                        .mc()
-                        .continuous()
-                });
-            }
-
-            #[inline(always)]
-            fn config_clock(&self, tbssel: Tbssel, div: TimerDiv) {
-                self.$tbxctl
-                    .write(|w| w.tbssel().bits(tbssel as u8).id().bits(div as u8));
-            }
-
-            #[inline(always)]
-            fn is_stopped(&self) -> bool {
-                self.$tbxctl.read().mc().is_stop()
-            }
-
-            #[inline(always)]
-            fn stop(&self) {
-                self.$tbxctl.write(|w| w.mc().stop());
-            }
-
-            #[inline(always)]
-            fn set_tbidex(&self, tbidex: TimerExDiv) {
-                self.$tbxex.write(|w| w.tbidex().bits(tbidex as u8));
-            }
-
-            #[inline(always)]
-            fn tbifg_rd(&self) -> bool {
-                self.$tbxctl.read().tbifg().bit()
-            }
-
-            #[inline(always)]
-            fn tbifg_clr(&self) {
-                self.$tbxctl.write(|w| w.tbifg().clear_bit());
-            }
-
-            #[inline(always)]
-            fn tbie_set(&self) {
-                self.$tbxctl.write(|w| w.tbie().set_bit());
-            }
-
-            #[inline(always)]
-            fn tbie_clr(&self) {
-                self.$tbxctl.write(|w| w.tbie().clear_bit());
-            }
-
-            #[inline(always)]
-            fn tbxiv_rd(&self) -> u16 {
-                self.$tbxiv.read().bits()
-            }
-        }
-
-        $(ccrn_impl!($TBx, $CCRn, $tbxcctln, $tbxccrn);)*
-    };
-}
-
-timerb_impl!(
-    TB0,
-    tb0,
-    tb0ctl,
-    tb0ex0,
-    tb0iv,
-    [CCR0, tb0cctl0, tb0ccr0],
-    [CCR1, tb0cctl1, tb0ccr1],
-    [CCR2, tb0cctl2, tb0ccr2],
-    [CCR3, tb0cctl3, tb0ccr3],
-    [CCR4, tb0cctl4, tb0ccr4],
-    [CCR5, tb0cctl5, tb0ccr5],
-    [CCR6, tb0cctl6, tb0ccr6]
-);
@@ -0,0 +1,232 @@
+//! Interrupt-owned peripheral handles
+//!
+//! Peripheral handles that are used from an interrupt handler are moved into an `IrqSlot`, a
+//! static that can be accessed safely from both the handler and the main program. The
+//! `irq_handler!` macro defines the `#[interrupt]` handler for a vector and borrows the slots it
+//! uses for the duration of the handler, checking at compile time that every peripheral handle
+//! listed as an interrupt source actually fires on that vector.
+//!
+//! ```ignore
+//! static VECTOR: IrqSlot<TBxIV<TB0>> = IrqSlot::new();
+//! static CAPTURE: IrqSlot<Capture<TB0, CCR1>> = IrqSlot::new();
+//! static LED: IrqSlot<Pin<P1, Pin0, Output>> = IrqSlot::new();
+//!
+//! // Sources before the `;` are checked against the vector, resources after it are not
+//! irq_handler!(TIMER0_B1, |vector: VECTOR, capture: CAPTURE; led: LED| {
+//!     if let CaptureVector::Capture1(cap) = vector.interrupt_vector() {
+//!         if cap.interrupt_capture(capture).is_ok() {
+//!             led.toggle().ok();
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! The handler does nothing if any of its slots is empty, so handles should be installed before
+//! their interrupts are enabled. `irq_handler!` requires the `abi_msp430_interrupt` feature in
+//! the crate that uses it. Passing `TIMER0_B1(wake_cpu)` as the vector wakes the CPU from low
+//! power mode when the handler returns.
+
+use crate::button::Button;
+use crate::capture::{self, Capture};
+use crate::gpio::{Input, Pin, PinNum, PxIV, P1, P2, P3, P4};
+use crate::rtc::{Rtc, RtcClockSrc};
+use crate::serial::{Rx, Tx};
+use crate::timer::{self, wheel::TimerWheel, CapCmp, CcrNum, SubTimer, Timer};
+use crate::watchdog::{IntervalMode, Wdt};
+use core::cell::{RefCell, RefMut};
+use critical_section::{CriticalSection, Mutex};
+use msp430fr247x as pac;
+use pac::Interrupt;
+
+#[doc(hidden)]
+pub use critical_section::with;
+
+/// Peripheral handle whose interrupt fires on a single vector
+pub trait InterruptSource {
+    /// Interrupt vector of the peripheral
+    const INTERRUPT: Interrupt;
+}
+
+/// Storage for a value shared between the main program and an interrupt handler
+pub struct IrqSlot<T>(Mutex<RefCell<Option<T>>>);
+
+impl<T> IrqSlot<T> {
+    /// Create an empty slot
+    #[inline]
+    pub const fn new() -> Self {
+        IrqSlot(Mutex::new(RefCell::new(None)))
+    }
+
+    /// Move a value into the slot, returning the previous value
+    #[inline]
+    pub fn install(&self, value: T) -> Option<T> {
+        with(|cs| self.0.borrow(cs).replace(Some(value)))
+    }
+
+    /// Move the value out of the slot, leaving it empty
+    #[inline]
+    pub fn take(&self) -> Option<T> {
+        with(|cs| self.0.borrow(cs).take())
+    }
+
+    /// Access the value in the slot inside a critical section. Returns `None` if the slot is empty.
+    #[inline]
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        with(|cs| self.0.borrow(cs).borrow_mut().as_mut().map(f))
+    }
+
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn borrow_mut<'cs>(&'cs self, cs: CriticalSection<'cs>) -> RefMut<'cs, Option<T>> {
+        self.0.borrow(cs).borrow_mut()
+    }
+}
+
+impl<T> Default for IrqSlot<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+pub const fn assert_source<T: InterruptSource>(_slot: &IrqSlot<T>, vector: Interrupt) {
+    assert!(
+        T::INTERRUPT as u16 == vector as u16,
+        "peripheral handle does not fire on this interrupt vector"
+    );
+}
+
+/// Define the interrupt handler for a vector, with mutable access to the contents of `IrqSlot`s.
+///
+/// Takes the vector name, then a list of `name: SLOT` bindings of interrupt sources, optionally
+/// followed by `;` and bindings of other shared resources, then the handler body. Each source
+/// must implement `InterruptSource` with the given vector, which is checked at compile time.
+#[macro_export]
+macro_rules! irq_handler {
+    (
+        $vector:ident $(($arg:ident))?,
+        |$($src:ident : $src_slot:path),* $(; $($res:ident : $res_slot:path),* $(,)?)?|
+        $body:block
+    ) => {
+        $(
+            const _: () = $crate::irq::assert_source(&$src_slot, $crate::pac::Interrupt::$vector);
+        )*
+
+        const _: () = {
+            use $crate::pac::interrupt;
+
+            #[interrupt$(($arg))?]
+            fn $vector() {
+                $crate::irq::with(|cs| {
+                    $(
+                        let mut $src = $src_slot.borrow_mut(cs);
+                        let ::core::option::Option::Some($src) = $src.as_mut() else {
+                            return;
+                        };
+                    )*
+                    $($(
+                        let mut $res = $res_slot.borrow_mut(cs);
+                        let ::core::option::Option::Some($res) = $res.as_mut() else {
+                            return;
+                        };
+                    )*)?
+                    $body
+                })
+            }
+        };
+    };
+}
+
+// Capture and compare interrupts of CCR0 have their own vector, the other sub-timers share the
+// vector of the main timer
+const fn tb0_ccr_vector(num: usize) -> Interrupt {
+    match num {
+        0 => Interrupt::TIMER0_B0,
+        _ => Interrupt::TIMER0_B1,
+    }
+}
+
+impl<C: CcrNum> InterruptSource for Capture<pac::TB0, C>
+where
+    pac::TB0: CapCmp<C>,
+{
+    const INTERRUPT: Interrupt = tb0_ccr_vector(C::NUM);
+}
+
+impl<C: CcrNum> InterruptSource for capture::measure::Measure<pac::TB0, C>
+where
+    pac::TB0: CapCmp<C>,
+{
+    const INTERRUPT: Interrupt = tb0_ccr_vector(C::NUM);
+}
+
+impl<C: CcrNum> InterruptSource for SubTimer<pac::TB0, C>
+where
+    pac::TB0: CapCmp<C>,
+{
+    const INTERRUPT: Interrupt = tb0_ccr_vector(C::NUM);
+}
+
+impl InterruptSource for Timer<pac::TB0> {
+    const INTERRUPT: Interrupt = Interrupt::TIMER0_B1;
+}
+
+impl InterruptSource for timer::TBxIV<pac::TB0> {
+    const INTERRUPT: Interrupt = Interrupt::TIMER0_B1;
+}
+
+impl InterruptSource for capture::TBxIV<pac::TB0> {
+    const INTERRUPT: Interrupt = Interrupt::TIMER0_B1;
+}
+
+impl<C: CcrNum, const N: usize> InterruptSource for TimerWheel<pac::TB0, C, N>
+where
+    pac::TB0: CapCmp<C>,
+{
+    const INTERRUPT: Interrupt = Interrupt::TIMER0_B1;
+}
+
+macro_rules! port_source {
+    ($($Px:ident => $vector:ident),*) => {
+        $(
+            impl InterruptSource for PxIV<$Px> {
+                const INTERRUPT: Interrupt = Interrupt::$vector;
+            }
+
+            impl<PIN: PinNum, PULL> InterruptSource for Pin<$Px, PIN, Input<PULL>> {
+                const INTERRUPT: Interrupt = Interrupt::$vector;
+            }
+
+            impl<PIN: PinNum, PULL> InterruptSource for Button<$Px, PIN, PULL> {
+                const INTERRUPT: Interrupt = Interrupt::$vector;
+            }
+        )*
+    };
+}
+
+port_source!(P1 => PORT1, P2 => PORT2, P3 => PORT3, P4 => PORT4);
+
+macro_rules! usci_source {
+    ($($Usci:ident => $vector:ident),*) => {
+        $(
+            impl InterruptSource for Tx<pac::$Usci> {
+                const INTERRUPT: Interrupt = Interrupt::$vector;
+            }
+
+            impl InterruptSource for Rx<pac::$Usci> {
+                const INTERRUPT: Interrupt = Interrupt::$vector;
+            }
+        )*
+    };
+}
+
+usci_source!(E_USCI_A0 => EUSCI_A0, E_USCI_A1 => EUSCI_A1);
+
+impl<SRC: RtcClockSrc> InterruptSource for Rtc<SRC> {
+    const INTERRUPT: Interrupt = Interrupt::RTC;
+}
+
+impl InterruptSource for Wdt<IntervalMode> {
+    const INTERRUPT: Interrupt = Interrupt::WDT;
+}
@@ -27,11 +27,19 @@
 #![feature(asm_experimental_arch)]
 #![deny(missing_docs)]
 
+#[cfg(feature = "async")]
+pub mod asynch;
 pub mod batch_gpio;
+pub mod button;
+pub mod calc;
 pub mod capture;
 pub mod clock;
+pub mod delay;
 pub mod fram;
 pub mod gpio;
+pub mod irq;
+#[cfg(feature = "rtic-monotonic")]
+pub mod monotonic;
 pub mod pmm;
 pub mod prelude;
 pub mod pwm;
@@ -40,6 +48,7 @@ pub mod serial;
 pub mod timer;
 pub mod watchdog;
 
+mod hw_impls;
 mod hw_traits;
 mod util;
 
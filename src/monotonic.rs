@@ -0,0 +1,111 @@
+//! RTIC monotonic timer
+//!
+//! Implements `rtic_monotonic::Monotonic` on top of a TimerB peripheral running in continuous
+//! mode. The hardware counter is extended to 32 bits by counting overflows via the TBIFG
+//! interrupt, and one of the sub-timers is used as the compare register for scheduling.
+//!
+//! Both the overflow and the sub-timer compare interrupts fire on the `TIMERx_B1` vector, so the
+//! monotonic must be bound to that interrupt. The tick rate of the monotonic is the tick frequency
+//! of the `TimerConfig` used to create the timer, which must match the `FREQ` parameter.
+
+use crate::hw_traits::timerb::CCRn;
+use crate::timer::{extend_count, CapCmp, SubTimer, Timer, TimerPeriph};
+use rtic_monotonic::Monotonic;
+
+/// Instant type of the monotonic, in timer ticks
+pub type Instant<const FREQ: u32> = fugit::TimerInstantU32<FREQ>;
+/// Duration type of the monotonic, in timer ticks
+pub type Duration<const FREQ: u32> = fugit::TimerDurationU32<FREQ>;
+
+/// Monotonic timer with a 32-bit tick count, built from a main timer and a sub-timer of the same
+/// peripheral
+pub struct TimerMonotonic<T: TimerPeriph + CapCmp<C>, C, const FREQ: u32> {
+    timer: Timer<T>,
+    _subtimer: SubTimer<T, C>,
+    overflows: u32,
+}
+
+impl<T: TimerPeriph + CapCmp<C>, C, const FREQ: u32> TimerMonotonic<T, C, FREQ> {
+    /// Create the monotonic out of the main timer and a sub-timer. The timer is reset and started
+    /// by RTIC when the monotonic is initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick frequency of the timer does not equal `FREQ`.
+    #[inline]
+    pub fn new(timer: Timer<T>, subtimer: SubTimer<T, C>) -> Self {
+        assert_eq!(timer.tick_freq(), FREQ);
+        TimerMonotonic {
+            timer,
+            _subtimer: subtimer,
+            overflows: 0,
+        }
+    }
+}
+
+impl<T: TimerPeriph + CapCmp<C>, C, const FREQ: u32> Monotonic for TimerMonotonic<T, C, FREQ> {
+    // The overflow interrupt must keep firing to extend the counter
+    const DISABLE_INTERRUPT_ON_EMPTY_QUEUE: bool = false;
+
+    type Instant = Instant<FREQ>;
+    type Duration = Duration<FREQ>;
+
+    #[inline]
+    fn now(&mut self) -> Self::Instant {
+        let timer = unsafe { T::steal() };
+        let count = self.timer.count();
+        let cntl = self.timer.counter_length();
+        Instant::from_ticks(extend_count(&timer, cntl, count, self.overflows))
+    }
+
+    /// Instants more than one counter period in the future cause a spurious compare interrupt,
+    /// after which RTIC sets the compare value again.
+    #[inline]
+    fn set_compare(&mut self, instant: Self::Instant) {
+        let timer = unsafe { T::steal() };
+        let max = self.timer.counter_length().max();
+        <T as CCRn<C>>::set_ccrn(&timer, instant.ticks() as u16 & max);
+    }
+
+    #[inline]
+    fn clear_compare_flag(&mut self) {
+        let timer = unsafe { T::steal() };
+        <T as CCRn<C>>::ccifg_clr(&timer);
+    }
+
+    #[inline(always)]
+    fn zero() -> Self::Instant {
+        Instant::from_ticks(0)
+    }
+
+    #[inline]
+    unsafe fn reset(&mut self) {
+        let timer = T::steal();
+        self.overflows = 0;
+        timer.continuous();
+        timer.tbie_set();
+        <T as CCRn<C>>::ccifg_clr(&timer);
+        <T as CCRn<C>>::ccie_set(&timer);
+    }
+
+    #[inline]
+    fn on_interrupt(&mut self) {
+        let timer = unsafe { T::steal() };
+        if timer.tbifg_rd() {
+            timer.tbifg_clr();
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+    }
+
+    #[inline]
+    fn enable_timer(&mut self) {
+        let timer = unsafe { T::steal() };
+        <T as CCRn<C>>::ccie_set(&timer);
+    }
+
+    #[inline]
+    fn disable_timer(&mut self) {
+        let timer = unsafe { T::steal() };
+        <T as CCRn<C>>::ccie_clr(&timer);
+    }
+}
@@ -5,6 +5,11 @@
 //!
 //! Each PWM pin starts off in an "uninitialized" state and must be initialized by passing in the
 //! appropriate alternate-function GPIO pin. Only initialized pins can be used for PWM.
+//!
+//! PWM ports are edge-aligned by default, with the timer in up mode. Center-aligned PWM ports run
+//! the timer in up/down mode, halving the PWM frequency for the same period. In center-aligned
+//! ports, adjacent pins (1 and 2, 3 and 4, 5 and 6) can also be paired up into complementary
+//! outputs separated by a dead time.
 
 use crate::gpio::{
      Alternate2, ChangeSelectBits, Output, Pin,
@@ -17,8 +22,8 @@ use embedded_hal::PwmPin;
 use msp430fr247x as pac;
 
 pub use crate::timer::{
-    CapCmp, TimerConfig, TimerDiv, TimerExDiv, TimerPeriph, CCR0, CCR1, CCR2, CCR3, CCR4, CCR5,
-    CCR6,
+    CapCmp, CompareGroup, CompareLoad, TimerConfig, TimerDiv, TimerExDiv, TimerPeriph, CCR0, CCR1,
+    CCR2, CCR3, CCR4, CCR5, CCR6,
 };
 
 #[doc(hidden)]
@@ -82,6 +87,102 @@ impl PwmPeriph<CCR6> for pac::TB0 {
     const ALT: Alt = Alt::Alt2;
 }
 
+/// Alignment of the PWM signals with respect to the period
+pub trait Alignment: sealed::SealedAlignment {
+    #[doc(hidden)]
+    const OUTMOD: Outmod;
+
+    #[doc(hidden)]
+    fn start<T: TimerPeriph>(timer: &T);
+
+    // Length of a PWM period in timer ticks for a given CCR0 value, and vice versa
+    #[doc(hidden)]
+    fn period_ticks(ccr0: u16) -> u32;
+    #[doc(hidden)]
+    fn ccr0(period_ticks: u32) -> u16;
+
+    // Set the duty cycle of a PWM output, handling the duty cycles the output mode can't produce
+    #[doc(hidden)]
+    fn set_duty<T: CapCmp<C> + CapCmp<CCR0>, C>(timer: &T, duty: u16);
+}
+
+/// Typestate for PWM ports with edge-aligned signals, where all signals go high at the start of
+/// the period
+pub struct EdgeAligned;
+/// Typestate for PWM ports with center-aligned signals, where all signals are centered around the
+/// start of the period
+pub struct CenterAligned;
+
+impl Alignment for EdgeAligned {
+    const OUTMOD: Outmod = Outmod::ResetSet;
+
+    #[inline(always)]
+    fn start<T: TimerPeriph>(timer: &T) {
+        timer.upmode();
+    }
+
+    // Up mode counts from 0 to CCR0 inclusive
+    #[inline(always)]
+    fn period_ticks(ccr0: u16) -> u32 {
+        ccr0 as u32 + 1
+    }
+
+    #[inline(always)]
+    fn ccr0(period_ticks: u32) -> u16 {
+        period_ticks.clamp(2, 0x1_0000) as u16 - 1
+    }
+
+    #[inline(always)]
+    fn set_duty<T: CapCmp<C> + CapCmp<CCR0>, C>(timer: &T, duty: u16) {
+        CCRn::<C>::set_ccrn(timer, duty);
+    }
+}
+
+impl Alignment for CenterAligned {
+    // In up/down mode the output is reset at the top of the count and toggled when crossing
+    // CCRn on either side, so it stays high while the counter is below CCRn
+    const OUTMOD: Outmod = Outmod::ToggleReset;
+
+    #[inline(always)]
+    fn start<T: TimerPeriph>(timer: &T) {
+        timer.updown();
+    }
+
+    // Up/down mode counts from 0 to CCR0 and back down
+    #[inline(always)]
+    fn period_ticks(ccr0: u16) -> u32 {
+        2 * ccr0 as u32
+    }
+
+    #[inline(always)]
+    fn ccr0(period_ticks: u32) -> u16 {
+        (period_ticks / 2).clamp(1, 0xFFFF) as u16
+    }
+
+    // In up/down mode, a compare value of 0 or the period only toggles the output once per cycle,
+    // so those cases are driven as constant levels instead. The compare value is still written so
+    // the duty cycle can be read back.
+    #[inline]
+    fn set_duty<T: CapCmp<C> + CapCmp<CCR0>, C>(timer: &T, duty: u16) {
+        let period = CCRn::<CCR0>::get_ccrn(timer);
+        CCRn::<C>::set_ccrn(timer, duty);
+        if duty == 0 || duty >= period {
+            CCRn::<C>::config_out(timer, duty != 0);
+        } else {
+            CCRn::<C>::config_outmod(timer, Self::OUTMOD);
+        }
+    }
+}
+
+mod sealed {
+    use super::*;
+
+    pub trait SealedAlignment {}
+
+    impl SealedAlignment for EdgeAligned {}
+    impl SealedAlignment for CenterAligned {}
+}
+
 fn setup_pwm<T: TimerPeriph>(timer: &T, config: TimerConfig<T>, period: u16) {
     config.write_regs(timer);
     CCRn::<CCR0>::set_ccrn(timer, period);
@@ -89,22 +190,37 @@ fn setup_pwm<T: TimerPeriph>(timer: &T, config: TimerConfig<T>, period: u16) {
 }
 
 /// Collection of uninitialized PWM pins derived from timer peripheral with 3 capture-compare registers
-pub struct PwmParts3<T: CapCmpTimer3> {
+pub struct PwmParts3<T: CapCmpTimer3, A: Alignment = EdgeAligned> {
     /// PWM pin 1 (derived from capture-compare register 1)
-    pub pwm1: PwmUninit<T, CCR1>,
+    pub pwm1: PwmUninit<T, CCR1, A>,
     /// PWM pin 2 (derived from capture-compare register 2)
-    pub pwm2: PwmUninit<T, CCR2>,
+    pub pwm2: PwmUninit<T, CCR2, A>,
 }
 
 impl<T: CapCmpTimer3> PwmParts3<T> {
-    /// Create uninitialized PWM pins with the same period
+    /// Create uninitialized edge-aligned PWM pins with the same period
     pub fn new(timer: T, config: TimerConfig<T>, period: u16) -> Self {
+        Self::setup(timer, config, period)
+    }
+}
+
+impl<T: CapCmpTimer3> PwmParts3<T, CenterAligned> {
+    /// Create uninitialized center-aligned PWM pins with the same period. The timer counts up to
+    /// the period and back down, so the PWM frequency is half that of edge-aligned PWM. Duty
+    /// cycles of 0 and the period are driven as constant levels.
+    pub fn new_center_aligned(timer: T, config: TimerConfig<T>, period: u16) -> Self {
+        Self::setup(timer, config, period)
+    }
+}
+
+impl<T: CapCmpTimer3, A: Alignment> PwmParts3<T, A> {
+    fn setup(timer: T, config: TimerConfig<T>, period: u16) -> Self {
         setup_pwm(&timer, config, period);
         // Configure PWM ports
-        CCRn::<CCR1>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR2>::config_outmod(&timer, Outmod::ResetSet);
+        CCRn::<CCR1>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR2>::config_outmod(&timer, A::OUTMOD);
         // Start the timer to run PWM
-        timer.upmode();
+        A::start(&timer);
         Self {
             pwm1: PwmUninit::new(),
             pwm2: PwmUninit::new(),
@@ -113,34 +229,63 @@ impl<T: CapCmpTimer3> PwmParts3<T> {
 }
 
 /// Collection of uninitialized PWM pins derived from timer peripheral with 7 capture-compare registers
-pub struct PwmParts7<T: CapCmpTimer7> {
+pub struct PwmParts7<T: CapCmpTimer7, A: Alignment = EdgeAligned> {
     /// PWM pin 1 (derived from capture-compare register 1)
-    pub pwm1: PwmUninit<T, CCR1>,
+    pub pwm1: PwmUninit<T, CCR1, A>,
     /// PWM pin 2 (derived from capture-compare register 2)
-    pub pwm2: PwmUninit<T, CCR2>,
+    pub pwm2: PwmUninit<T, CCR2, A>,
     /// PWM pin 3 (derived from capture-compare register 3)
-    pub pwm3: PwmUninit<T, CCR3>,
+    pub pwm3: PwmUninit<T, CCR3, A>,
     /// PWM pin 4 (derived from capture-compare register 4)
-    pub pwm4: PwmUninit<T, CCR4>,
+    pub pwm4: PwmUninit<T, CCR4, A>,
     /// PWM pin 5 (derived from capture-compare register 5)
-    pub pwm5: PwmUninit<T, CCR5>,
+    pub pwm5: PwmUninit<T, CCR5, A>,
     /// PWM pin 6 (derived from capture-compare register 6)
-    pub pwm6: PwmUninit<T, CCR6>,
+    pub pwm6: PwmUninit<T, CCR6, A>,
+    /// Handle for updating the duty cycles of multiple PWM pins at once
+    pub group: PwmGroup<T>,
+    /// Handle for controlling the period of all PWM pins
+    pub timer: PwmTimer<T, A>,
 }
 
 impl<T: CapCmpTimer7> PwmParts7<T> {
-    /// Create uninitialized PWM pins with the same period
+    /// Create uninitialized edge-aligned PWM pins with the same period
     pub fn new(timer: T, config: TimerConfig<T>, period: u16) -> Self {
+        Self::setup(timer, config, period)
+    }
+}
+
+impl<T: CapCmpTimer7> PwmParts7<T, CenterAligned> {
+    /// Create uninitialized center-aligned PWM pins with the same period. The timer counts up to
+    /// the period and back down, so the PWM frequency is half that of edge-aligned PWM. Duty
+    /// cycles of 0 and the period are driven as constant levels.
+    pub fn new_center_aligned(timer: T, config: TimerConfig<T>, period: u16) -> Self {
+        Self::setup(timer, config, period)
+    }
+}
+
+impl<T: CapCmpTimer7, A: Alignment> PwmParts7<T, A> {
+    fn setup(timer: T, config: TimerConfig<T>, period: u16) -> Self {
+        let pwm_timer = PwmTimer {
+            _timer: PhantomData,
+            _align: PhantomData,
+            clk_freq: config.clk_freq(),
+            tick_freq: config.tick_freq(),
+        };
+        let group = PwmGroup {
+            _timer: PhantomData,
+            ccr0_grouped: matches!(config.clgrp, CompareGroup::All),
+        };
         setup_pwm(&timer, config, period);
         // Configure PWM ports
-        CCRn::<CCR1>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR2>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR3>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR4>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR5>::config_outmod(&timer, Outmod::ResetSet);
-        CCRn::<CCR6>::config_outmod(&timer, Outmod::ResetSet);
+        CCRn::<CCR1>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR2>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR3>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR4>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR5>::config_outmod(&timer, A::OUTMOD);
+        CCRn::<CCR6>::config_outmod(&timer, A::OUTMOD);
         // Start the timer to run PWM
-        timer.upmode();
+        A::start(&timer);
         Self {
             pwm1: PwmUninit::new(),
             pwm2: PwmUninit::new(),
@@ -148,45 +293,364 @@ impl<T: CapCmpTimer7> PwmParts7<T> {
             pwm4: PwmUninit::new(),
             pwm5: PwmUninit::new(),
             pwm6: PwmUninit::new(),
+            group,
+            timer: pwm_timer,
+        }
+    }
+}
+
+/// Handle for updating the duty cycles of all PWM pins of a port at once
+///
+/// Combined with `TimerConfig::compare_group` and a compare load event other than `Immediate`, the
+/// grouped pins all switch to their new duty cycles on the same period boundary.
+pub struct PwmGroup<T: CapCmpTimer7> {
+    _timer: PhantomData<T>,
+    // Whether CCR0 shares a compare latch group with the duty cycles
+    ccr0_grouped: bool,
+}
+
+impl<T: CapCmpTimer7> PwmGroup<T> {
+    /// Set the event upon which new duty cycles and periods take effect, for all capture-compare
+    /// registers. When the latches are grouped, only the setting of the lowest register in each
+    /// group matters.
+    #[inline]
+    pub fn set_compare_load(&mut self, load: CompareLoad) {
+        let timer = unsafe { T::steal() };
+        CCRn::<CCR0>::config_clld(&timer, load);
+        CCRn::<CCR1>::config_clld(&timer, load);
+        CCRn::<CCR2>::config_clld(&timer, load);
+        CCRn::<CCR3>::config_clld(&timer, load);
+        CCRn::<CCR4>::config_clld(&timer, load);
+        CCRn::<CCR5>::config_clld(&timer, load);
+        CCRn::<CCR6>::config_clld(&timer, load);
+    }
+
+    /// Update the duty cycles of PWM pins 1 to 6, which are passed to the closure as elements 0
+    /// to 5 of an array holding the current duty cycles.
+    ///
+    /// All six capture-compare registers are written, even when their duty cycle doesn't change,
+    /// since grouped compare latches only load once every register of the group has been written.
+    /// With `CompareGroup::All`, CCR0 is rewritten with the current period for the same reason.
+    /// With grouped latches and a compare load event other than `Immediate`, the new duty cycles
+    /// all take effect on the first load event after this returns, without blocking. Otherwise the
+    /// registers take effect one by one as they are written, so a period may mix old and new duty
+    /// cycles. On center-aligned ports, the duty cycles must lie strictly between 0 and the
+    /// period, since the switch to a constant level can't be synchronized to the compare load.
+    pub fn update_group<F: FnOnce(&mut [u16; 6])>(&mut self, f: F) {
+        let timer = unsafe { T::steal() };
+        let mut duties = [
+            CCRn::<CCR1>::get_ccrn(&timer),
+            CCRn::<CCR2>::get_ccrn(&timer),
+            CCRn::<CCR3>::get_ccrn(&timer),
+            CCRn::<CCR4>::get_ccrn(&timer),
+            CCRn::<CCR5>::get_ccrn(&timer),
+            CCRn::<CCR6>::get_ccrn(&timer),
+        ];
+        f(&mut duties);
+
+        if self.ccr0_grouped {
+            let period = CCRn::<CCR0>::get_ccrn(&timer);
+            CCRn::<CCR0>::set_ccrn(&timer, period);
+        }
+        CCRn::<CCR1>::set_ccrn(&timer, duties[0]);
+        CCRn::<CCR2>::set_ccrn(&timer, duties[1]);
+        CCRn::<CCR3>::set_ccrn(&timer, duties[2]);
+        CCRn::<CCR4>::set_ccrn(&timer, duties[3]);
+        CCRn::<CCR5>::set_ccrn(&timer, duties[4]);
+        CCRn::<CCR6>::set_ccrn(&timer, duties[5]);
+    }
+}
+
+/// PWM channel of a `PwmTimer`, corresponding to PWM pins 1 to 6
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PwmChannel {
+    /// PWM pin 1 (capture-compare register 1)
+    Pwm1,
+    /// PWM pin 2 (capture-compare register 2)
+    Pwm2,
+    /// PWM pin 3 (capture-compare register 3)
+    Pwm3,
+    /// PWM pin 4 (capture-compare register 4)
+    Pwm4,
+    /// PWM pin 5 (capture-compare register 5)
+    Pwm5,
+    /// PWM pin 6 (capture-compare register 6)
+    Pwm6,
+}
+
+#[inline]
+fn channel_ccr<T: CapCmpTimer7>(timer: &T, channel: PwmChannel) -> u16 {
+    match channel {
+        PwmChannel::Pwm1 => CCRn::<CCR1>::get_ccrn(timer),
+        PwmChannel::Pwm2 => CCRn::<CCR2>::get_ccrn(timer),
+        PwmChannel::Pwm3 => CCRn::<CCR3>::get_ccrn(timer),
+        PwmChannel::Pwm4 => CCRn::<CCR4>::get_ccrn(timer),
+        PwmChannel::Pwm5 => CCRn::<CCR5>::get_ccrn(timer),
+        PwmChannel::Pwm6 => CCRn::<CCR6>::get_ccrn(timer),
+    }
+}
+
+#[inline]
+fn channel_outmod<T: CapCmpTimer7>(timer: &T, channel: PwmChannel, outmod: Option<Outmod>) {
+    fn apply<T: CapCmp<C>, C>(timer: &T, outmod: Option<Outmod>) {
+        match outmod {
+            Some(outmod) => CCRn::<C>::config_outmod(timer, outmod),
+            None => CCRn::<C>::config_out(timer, false),
+        }
+    }
+
+    match channel {
+        PwmChannel::Pwm1 => apply::<T, CCR1>(timer, outmod),
+        PwmChannel::Pwm2 => apply::<T, CCR2>(timer, outmod),
+        PwmChannel::Pwm3 => apply::<T, CCR3>(timer, outmod),
+        PwmChannel::Pwm4 => apply::<T, CCR4>(timer, outmod),
+        PwmChannel::Pwm5 => apply::<T, CCR5>(timer, outmod),
+        PwmChannel::Pwm6 => apply::<T, CCR6>(timer, outmod),
+    }
+}
+
+// Rescale the duty cycles of a high side channel and the next channel from the `old` to the `new`
+// period. If the channels form a complementary pair, the dead time is kept instead of rescaled.
+fn rescale_pair<T: CapCmp<H> + CapCmp<L>, H, L>(timer: &T, old: u16, new: u16) {
+    // Nothing to scale relative to if the old period was 0
+    let scale = |duty: u16| {
+        (duty as u32 * new as u32)
+            .checked_div(old as u32)
+            .map(|scaled| scaled.min(0xFFFF) as u16)
+    };
+    let high = CCRn::<H>::get_ccrn(timer);
+    let low = CCRn::<L>::get_ccrn(timer);
+
+    if is_complementary_low::<T, L>(timer) {
+        if let Some(duty) = scale(high) {
+            let dead_time = low.saturating_sub(high);
+            set_complementary::<T, H, L>(timer, new, duty, duty.saturating_add(dead_time));
+        }
+    } else {
+        if let Some(duty) = scale(high) {
+            CCRn::<H>::set_ccrn(timer, duty);
+        }
+        if let Some(duty) = scale(low) {
+            CCRn::<L>::set_ccrn(timer, duty);
         }
     }
 }
 
+/// Handle for the timer shared by all PWM pins of a port, allowing the period and clock divider to
+/// be changed at runtime
+///
+/// Periods are expressed as PWM frequencies in Hertz. Changing the period or clock divider rescales
+/// the duty cycles of all pins, so that their ratio to the period stays the same. Complementary
+/// pairs keep their dead time in timer ticks.
+pub struct PwmTimer<T: CapCmpTimer7, A: Alignment> {
+    _timer: PhantomData<T>,
+    _align: PhantomData<A>,
+    clk_freq: u32,
+    tick_freq: u32,
+}
+
+impl<T: CapCmpTimer7, A: Alignment> PwmTimer<T, A> {
+    /// Frequency of the timer ticks, after the clock dividers are applied
+    #[inline]
+    pub fn tick_freq(&self) -> u32 {
+        self.tick_freq
+    }
+
+    /// Period of the PWM signals in timer ticks
+    #[inline]
+    pub fn period_ticks(&self) -> u32 {
+        let timer = unsafe { T::steal() };
+        A::period_ticks(CCRn::<CCR0>::get_ccrn(&timer))
+    }
+
+    /// Set the period of the PWM signals in timer ticks. Periods that don't fit in the counter are
+    /// clamped.
+    ///
+    /// The period is written before the duty cycles when it grows and after them when it shrinks,
+    /// so no duty cycle exceeds the period in between, which would drive the pin high for a whole
+    /// period.
+    pub fn set_period_ticks(&mut self, ticks: u32) {
+        let timer = unsafe { T::steal() };
+        let old = CCRn::<CCR0>::get_ccrn(&timer);
+        let new = A::ccr0(ticks);
+        if new >= old {
+            CCRn::<CCR0>::set_ccrn(&timer, new);
+        }
+        rescale_pair::<T, CCR1, CCR2>(&timer, old, new);
+        rescale_pair::<T, CCR3, CCR4>(&timer, old, new);
+        rescale_pair::<T, CCR5, CCR6>(&timer, old, new);
+        if new < old {
+            CCRn::<CCR0>::set_ccrn(&timer, new);
+        }
+    }
+
+    /// Change the clock dividers of the timer while keeping the PWM frequency as close as
+    /// possible. The timer is stopped while the dividers change, as the user's guide requires,
+    /// then restarted with its counter reset if it was running.
+    pub fn set_clk_div(&mut self, div: TimerDiv, ex_div: TimerExDiv) {
+        let freq = embedded_hal::Pwm::get_period(self);
+        let timer = unsafe { T::steal() };
+        let running = !timer.is_stopped();
+        timer.stop();
+        timer.set_tbidex(ex_div);
+        // TBCLR also resets the divider logic
+        timer.set_id_reset(div);
+        self.tick_freq = self.clk_freq / (1 << (div as u32)) / (ex_div as u32 + 1);
+        embedded_hal::Pwm::set_period(self, freq);
+        if running {
+            A::start(&timer);
+        }
+    }
+
+    /// Set the duty cycle of a channel as a percentage of the period
+    #[inline]
+    pub fn set_duty_percent(&mut self, channel: PwmChannel, percent: u8) {
+        self.set_duty_permille(channel, percent as u16 * 10);
+    }
+
+    /// Set the duty cycle of a channel in thousandths of the period
+    #[inline]
+    pub fn set_duty_permille(&mut self, channel: PwmChannel, permille: u16) {
+        let duty = permille_to_duty(embedded_hal::Pwm::get_max_duty(self), permille);
+        embedded_hal::Pwm::set_duty(self, channel, duty);
+    }
+}
+
+impl<T: CapCmpTimer7, A: Alignment> embedded_hal::Pwm for PwmTimer<T, A> {
+    type Channel = PwmChannel;
+    /// PWM frequency in Hertz
+    type Time = u32;
+    /// Number of cycles
+    type Duty = u16;
+
+    /// Drives the channel output low. On center-aligned ports, setting the duty cycle enables the
+    /// channel again.
+    #[inline]
+    fn disable(&mut self, channel: Self::Channel) {
+        let timer = unsafe { T::steal() };
+        channel_outmod(&timer, channel, None);
+    }
+
+    /// Restores the PWM output mode of the channel. Complementary low side pins must be
+    /// re-enabled through `ComplementaryPwm` instead.
+    #[inline]
+    fn enable(&mut self, channel: Self::Channel) {
+        let timer = unsafe { T::steal() };
+        channel_outmod(&timer, channel, Some(A::OUTMOD));
+    }
+
+    #[inline]
+    fn get_period(&self) -> Self::Time {
+        self.tick_freq.checked_div(self.period_ticks()).unwrap_or(0)
+    }
+
+    #[inline]
+    fn get_duty(&self, channel: Self::Channel) -> Self::Duty {
+        let timer = unsafe { T::steal() };
+        channel_ccr(&timer, channel)
+    }
+
+    /// Maximum valid duty is equal to the period register.
+    #[inline]
+    fn get_max_duty(&self) -> Self::Duty {
+        let timer = unsafe { T::steal() };
+        CCRn::<CCR0>::get_ccrn(&timer)
+    }
+
+    #[inline]
+    fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) {
+        let timer = unsafe { T::steal() };
+        match channel {
+            PwmChannel::Pwm1 => A::set_duty::<T, CCR1>(&timer, duty),
+            PwmChannel::Pwm2 => A::set_duty::<T, CCR2>(&timer, duty),
+            PwmChannel::Pwm3 => A::set_duty::<T, CCR3>(&timer, duty),
+            PwmChannel::Pwm4 => A::set_duty::<T, CCR4>(&timer, duty),
+            PwmChannel::Pwm5 => A::set_duty::<T, CCR5>(&timer, duty),
+            PwmChannel::Pwm6 => A::set_duty::<T, CCR6>(&timer, duty),
+        }
+    }
+
+    /// Set the PWM frequency in Hertz. Frequencies too low for the counter are clamped to the
+    /// longest possible period.
+    #[inline]
+    fn set_period<P: Into<Self::Time>>(&mut self, period: P) {
+        let freq = period.into();
+        let ticks = self.tick_freq.checked_div(freq).unwrap_or(u32::MAX);
+        self.set_period_ticks(ticks);
+    }
+}
+
+#[inline]
+fn permille_to_duty(max: u16, permille: u16) -> u16 {
+    (max as u32 * permille.min(1000) as u32 / 1000) as u16
+}
+
+/// Duty cycle helpers expressed as fractions of the period, which stay correct after the period
+/// changes
+pub trait PwmDuty: PwmPin<Duty = u16> {
+    /// Set the duty cycle as a percentage of the period
+    #[inline]
+    fn set_duty_percent(&mut self, percent: u8) {
+        self.set_duty_permille(percent as u16 * 10);
+    }
+
+    /// Set the duty cycle in thousandths of the period
+    #[inline]
+    fn set_duty_permille(&mut self, permille: u16) {
+        let duty = permille_to_duty(self.get_max_duty(), permille);
+        self.set_duty(duty);
+    }
+
+    /// Duty cycle in thousandths of the period
+    #[inline]
+    fn get_duty_permille(&self) -> u16 {
+        match self.get_max_duty() {
+            0 => 0,
+            max => (self.get_duty() as u32 * 1000 / max as u32).min(1000) as u16,
+        }
+    }
+}
+
+impl<P: PwmPin<Duty = u16>> PwmDuty for P {}
+
 /// Uninitialized PWM pin
-pub struct PwmUninit<T, C>(PhantomData<T>, PhantomData<C>);
+pub struct PwmUninit<T, C, A = EdgeAligned>(PhantomData<T>, PhantomData<C>, PhantomData<A>);
 
-impl<T: PwmPeriph<C>, C> PwmUninit<T, C> {
+impl<T: PwmPeriph<C>, C, A: Alignment> PwmUninit<T, C, A> {
     /// Initialized the PWM pin by passing in the appropriately configured GPIO pin
-    pub fn init(self, pin: T::Gpio) -> Pwm<T, C> {
+    pub fn init(self, pin: T::Gpio) -> Pwm<T, C, A> {
         Pwm {
             _timer: PhantomData,
             _ccrn: PhantomData,
+            _align: PhantomData,
             pin,
         }
     }
 }
 
-impl<T, C> PwmUninit<T, C> {
+impl<T, C, A> PwmUninit<T, C, A> {
     fn new() -> Self {
-        Self(PhantomData, PhantomData)
+        Self(PhantomData, PhantomData, PhantomData)
     }
 }
 
 /// An initialized Pwm pin
-pub struct Pwm<T: PwmPeriph<C>, C> {
+pub struct Pwm<T: PwmPeriph<C>, C, A: Alignment = EdgeAligned> {
     _timer: PhantomData<T>,
     _ccrn: PhantomData<C>,
+    _align: PhantomData<A>,
     pin: T::Gpio,
 }
 
-impl<T: PwmPeriph<C>, C> PwmPin for Pwm<T, C> {
+impl<T: PwmPeriph<C>, C, A: Alignment> PwmPin for Pwm<T, C, A> {
     /// Number of cycles
     type Duty = u16;
 
+    /// On center-aligned pins, duty cycles of 0 and the period are driven as constant levels.
     #[inline]
     fn set_duty(&mut self, duty: Self::Duty) {
         let timer = unsafe { T::steal() };
-        CCRn::<C>::set_ccrn(&timer, duty);
+        A::set_duty::<T, C>(&timer, duty);
     }
 
     #[inline]
@@ -213,3 +677,173 @@ impl<T: PwmPeriph<C>, C> PwmPin for Pwm<T, C> {
         T::to_alt(&mut self.pin);
     }
 }
+
+/// Capture-compare registers that drive the high side of a complementary PWM pair, with the next
+/// register driving the low side
+pub trait ComplementaryPair {
+    /// Capture-compare register of the low side
+    type Low;
+}
+
+impl ComplementaryPair for CCR1 {
+    type Low = CCR2;
+}
+
+impl ComplementaryPair for CCR3 {
+    type Low = CCR4;
+}
+
+impl ComplementaryPair for CCR5 {
+    type Low = CCR6;
+}
+
+impl<T: PwmPeriph<C> + PwmPeriph<C::Low>, C: ComplementaryPair> PwmUninit<T, C, CenterAligned> {
+    /// Initialize a complementary PWM pair out of this pin and the next one. The high side pin is
+    /// high for the duty cycle and the low side pin is high for the rest of the period, minus the
+    /// dead time on either side, during which both pins are low. The dead time is in timer ticks.
+    pub fn init_complementary(
+        self,
+        low: PwmUninit<T, C::Low, CenterAligned>,
+        high_pin: <T as PwmPeriph<C>>::Gpio,
+        low_pin: <T as PwmPeriph<C::Low>>::Gpio,
+        dead_time: u16,
+    ) -> ComplementaryPwm<T, C> {
+        let _ = low;
+        let mut pwm = ComplementaryPwm {
+            _timer: PhantomData,
+            _ccrn: PhantomData,
+            high_pin,
+            low_pin,
+            dead_time,
+        };
+        pwm.set_duty(0);
+        pwm
+    }
+}
+
+// Set the compare values of a complementary pair, with the low side at `low`. Both compare values
+// are written even when a side is driven as a constant level, so the duty cycle and dead time can
+// be read back.
+fn set_complementary<T: CapCmp<H> + CapCmp<L>, H, L>(timer: &T, period: u16, duty: u16, low: u16) {
+    // In up/down mode, a compare value of 0 or the period only toggles the output once per
+    // cycle, so those cases are driven as constant levels instead.
+    // The high side is high while the counter is below its CCR.
+    CCRn::<H>::set_ccrn(timer, duty);
+    if duty == 0 || duty >= period {
+        CCRn::<H>::config_out(timer, duty != 0);
+    } else {
+        CCRn::<H>::config_outmod(timer, Outmod::ToggleReset);
+    }
+    // The low side is high while the counter is above its CCR. Constant levels are driven in
+    // `Out` mode and then held by a mode that only sets or resets the output, so the low side is
+    // never left in `Out` mode and `PwmTimer` can tell it apart from other channels.
+    CCRn::<L>::set_ccrn(timer, low);
+    if low == 0 || low >= period {
+        CCRn::<L>::config_out(timer, low == 0);
+        CCRn::<L>::config_outmod(timer, if low == 0 { Outmod::Set } else { Outmod::Reset });
+    } else {
+        CCRn::<L>::config_outmod(timer, Outmod::ToggleSet);
+    }
+}
+
+// Whether a channel is the low side of a complementary pair, going by the output modes only
+// `set_complementary` uses for the low side
+#[inline]
+fn is_complementary_low<T: CapCmp<L>, L>(timer: &T) -> bool {
+    matches!(
+        CCRn::<L>::outmod_rd(timer),
+        Outmod::ToggleSet | Outmod::Set | Outmod::Reset
+    )
+}
+
+/// Complementary pair of center-aligned PWM pins separated by a dead time
+pub struct ComplementaryPwm<T: PwmPeriph<C> + PwmPeriph<C::Low>, C: ComplementaryPair> {
+    _timer: PhantomData<T>,
+    _ccrn: PhantomData<C>,
+    high_pin: <T as PwmPeriph<C>>::Gpio,
+    low_pin: <T as PwmPeriph<C::Low>>::Gpio,
+    dead_time: u16,
+}
+
+impl<T: PwmPeriph<C> + PwmPeriph<C::Low>, C: ComplementaryPair> ComplementaryPwm<T, C> {
+    /// Dead time between the high and low side signals, in timer ticks
+    #[inline]
+    pub fn dead_time(&self) -> u16 {
+        self.dead_time
+    }
+
+    /// Set the dead time between the high and low side signals, in timer ticks
+    #[inline]
+    pub fn set_dead_time(&mut self, dead_time: u16) {
+        self.dead_time = dead_time;
+        self.set_duty(self.get_duty());
+    }
+}
+
+impl<T: PwmPeriph<C> + PwmPeriph<C::Low>, C: ComplementaryPair> PwmPin for ComplementaryPwm<T, C> {
+    /// Number of cycles
+    type Duty = u16;
+
+    /// Set the duty cycle of the high side. The low side is set so that the edges of the two
+    /// signals are separated by the dead time. If the duty cycle plus the dead time reaches the
+    /// period, the low side stays low.
+    #[inline]
+    fn set_duty(&mut self, duty: Self::Duty) {
+        let timer = unsafe { T::steal() };
+        let period = CCRn::<CCR0>::get_ccrn(&timer);
+        let low = duty.saturating_add(self.dead_time);
+        set_complementary::<T, C, C::Low>(&timer, period, duty, low);
+    }
+
+    #[inline]
+    fn get_duty(&self) -> Self::Duty {
+        let timer = unsafe { T::steal() };
+        CCRn::<C>::get_ccrn(&timer)
+    }
+
+    /// Maximum valid duty is equal to the period.
+    #[inline]
+    fn get_max_duty(&self) -> Self::Duty {
+        let timer = unsafe { T::steal() };
+        CCRn::<CCR0>::get_ccrn(&timer)
+    }
+
+    #[inline]
+    fn disable(&mut self) {
+        <T as PwmPeriph<C>>::to_gpio(&mut self.high_pin);
+        <T as PwmPeriph<C::Low>>::to_gpio(&mut self.low_pin);
+    }
+
+    #[inline]
+    fn enable(&mut self) {
+        <T as PwmPeriph<C>>::to_alt(&mut self.high_pin);
+        <T as PwmPeriph<C::Low>>::to_alt(&mut self.low_pin);
+    }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_1::pwm::{ErrorType, SetDutyCycle};
+
+    impl<T: PwmPeriph<C>, C, A: Alignment> ErrorType for Pwm<T, C, A> {
+        type Error = Infallible;
+    }
+
+    impl<T: PwmPeriph<C>, C, A: Alignment> SetDutyCycle for Pwm<T, C, A> {
+        /// Maximum duty cycle is equal to the period in timer cycles.
+        #[inline]
+        fn max_duty_cycle(&self) -> u16 {
+            let timer = unsafe { T::steal() };
+            CCRn::<CCR0>::get_ccrn(&timer)
+        }
+
+        #[inline]
+        fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+            let timer = unsafe { T::steal() };
+            A::set_duty::<T, C>(&timer, duty);
+            Ok(())
+        }
+    }
+}
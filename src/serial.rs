@@ -6,7 +6,8 @@
 //!
 //! The Tx and Rx pins are used to send and receive bytes via serial connection.
 
-use crate::clock::{Aclk, Clock, Smclk};
+use crate::calc::{calculate_baud_config, BaudConfig};
+use crate::clock::{Aclk, AclkSource, Clock, Smclk};
 use crate::gpio::{Alternate1, Pin, Pin4, Pin5, Pin6, P1, P2};
 use crate::hw_traits::eusci::{EUsciUart, UcaxStatw, Ucssel, UcxCtl0};
 use core::marker::PhantomData;
@@ -276,7 +277,7 @@ impl<USCI: SerialUsci> SerialConfig<USCI, NoClockSet> {
 
     /// Configure serial UART to use ACLK.
     #[inline(always)]
-    pub fn use_aclk(self, aclk: &Aclk) -> SerialConfig<USCI, ClockSet> {
+    pub fn use_aclk<SRC: AclkSource>(self, aclk: &Aclk<SRC>) -> SerialConfig<USCI, ClockSet> {
         serial_config!(
             self,
             ClockSet {
@@ -299,96 +300,6 @@ impl<USCI: SerialUsci> SerialConfig<USCI, NoClockSet> {
     }
 }
 
-struct BaudConfig {
-    br: u16,
-    brs: u8,
-    brf: u8,
-    ucos16: bool,
-}
-
-#[inline]
-fn calculate_baud_config(clk_freq: u32, bps: u32) -> BaudConfig {
-    // Prevent division by 0
-    let bps = bps.max(1);
-    // Ensure n stays within the 16 bit boundary
-    let n = (clk_freq / bps).max(1).min(0xFFFF);
-
-    let brs = lookup_brs(clk_freq, bps);
-
-    if n >= 16 {
-        let div = bps * 16;
-        // n / 16, but more precise
-        let br = (clk_freq / div) as u16;
-        // same as n % 16, but more precise
-        let brf = ((clk_freq % div) / bps) as u8;
-        BaudConfig {
-            ucos16: true,
-            br,
-            brf,
-            brs,
-        }
-    } else {
-        BaudConfig {
-            ucos16: false,
-            br: n as u16,
-            brf: 0,
-            brs,
-        }
-    }
-}
-
-#[inline(always)]
-fn lookup_brs(clk_freq: u32, bps: u32) -> u8 {
-    let modulo = clk_freq % bps;
-
-    // Fractional part lookup for the baud rate. Not extremely precise
-    if modulo * 19 < bps {
-        0x0
-    } else if modulo * 14 < bps {
-        0x1
-    } else if modulo * 12 < bps {
-        0x2
-    } else if modulo * 10 < bps {
-        0x4
-    } else if modulo * 8 < bps {
-        0x8
-    } else if modulo * 7 < bps {
-        0x10
-    } else if modulo * 6 < bps {
-        0x20
-    } else if modulo * 5 < bps {
-        0x11
-    } else if modulo * 4 < bps {
-        0x22
-    } else if modulo * 3 < bps {
-        0x44
-    } else if modulo * 11 < bps * 4 {
-        0x49
-    } else if modulo * 5 < bps * 2 {
-        0x4A
-    } else if modulo * 7 < bps * 3 {
-        0x92
-    } else if modulo * 2 < bps {
-        0x53
-    } else if modulo * 7 < bps * 4 {
-        0xAA
-    } else if modulo * 13 < bps * 8 {
-        0x6B
-    } else if modulo * 3 < bps * 2 {
-        0xAD
-    } else if modulo * 11 < bps * 8 {
-        0xD6
-    } else if modulo * 4 < bps * 3 {
-        0xBB
-    } else if modulo * 5 < bps * 4 {
-        0xDD
-    } else if modulo * 9 < bps * 8 {
-        0xEF
-    } else {
-        0xFD
-    }
-}
-
 impl<USCI: SerialUsci> SerialConfig<USCI, ClockSet> {
     #[inline]
     fn config_hw(self) {
@@ -425,6 +336,14 @@ impl<USCI: SerialUsci> SerialConfig<USCI, ClockSet> {
         (Tx(PhantomData), Rx(PhantomData))
     }
 
+    /// Perform hardware configuration and create a combined Tx and Rx serial interface from
+    /// appropriate GPIOs
+    #[inline]
+    pub fn serial<T: Into<USCI::TxPin>, R: Into<USCI::RxPin>>(self, tx: T, rx: R) -> Serial<USCI> {
+        let (tx, rx) = self.split(tx, rx);
+        Serial { tx, rx }
+    }
+
     /// Perform hardware configuration and create Tx pin from appropriate GPIO
     #[inline]
     pub fn tx_only<T: Into<USCI::TxPin>>(self, _tx: T) -> Tx<USCI> {
@@ -510,7 +429,30 @@ impl<USCI: SerialUsci> Rx<USCI> {
     }
 }
 
+/// Combined serial transmitter and receiver
+pub struct Serial<USCI: SerialUsci> {
+    /// Serial transmitter
+    pub tx: Tx<USCI>,
+    /// Serial receiver
+    pub rx: Rx<USCI>,
+}
+
+impl<USCI: SerialUsci> Serial<USCI> {
+    /// Combine Tx and Rx pins of the same USCI into a single serial interface
+    #[inline(always)]
+    pub fn join(tx: Tx<USCI>, rx: Rx<USCI>) -> Self {
+        Serial { tx, rx }
+    }
+
+    /// Split the serial interface into separate Tx and Rx pins
+    #[inline(always)]
+    pub fn split(self) -> (Tx<USCI>, Rx<USCI>) {
+        (self.tx, self.rx)
+    }
+}
+
 /// Serial receive errors
+#[derive(Debug)]
 pub enum RecvError {
     /// Framing error
     Framing,
@@ -548,3 +490,195 @@ impl<USCI: SerialUsci> Read<u8> for Rx<USCI> {
         }
     }
 }
+
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_nb::serial::{self, ErrorKind, ErrorType};
+
+    impl serial::Error for RecvError {
+        #[inline]
+        fn kind(&self) -> ErrorKind {
+            match self {
+                RecvError::Framing => ErrorKind::FrameFormat,
+                RecvError::Parity => ErrorKind::Parity,
+                RecvError::Overrun(_) => ErrorKind::Overrun,
+            }
+        }
+    }
+
+    impl<USCI: SerialUsci> ErrorType for Tx<USCI> {
+        type Error = Infallible;
+    }
+
+    impl<USCI: SerialUsci> serial::Write<u8> for Tx<USCI> {
+        /// Same caveats as the `embedded_hal` 0.2 implementation apply: the Tx buffer will be
+        /// empty but the FIFO may still be sending when `flush()` completes.
+        #[inline]
+        fn flush(&mut self) -> nb::Result<(), Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            if usci.txifg_rd() {
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+
+        #[inline]
+        fn write(&mut self, data: u8) -> nb::Result<(), Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            if usci.txifg_rd() {
+                usci.tx_wr(data);
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    impl<USCI: SerialUsci> ErrorType for Rx<USCI> {
+        type Error = RecvError;
+    }
+
+    impl<USCI: SerialUsci> serial::Read<u8> for Rx<USCI> {
+        #[inline]
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            Read::read(self)
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+mod eio {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_io::{ErrorKind, ErrorType, ReadReady, WriteReady};
+
+    impl embedded_io::Error for RecvError {
+        #[inline]
+        fn kind(&self) -> ErrorKind {
+            match self {
+                RecvError::Framing | RecvError::Parity => ErrorKind::InvalidData,
+                RecvError::Overrun(_) => ErrorKind::Other,
+            }
+        }
+    }
+
+    impl<USCI: SerialUsci> ErrorType for Tx<USCI> {
+        type Error = Infallible;
+    }
+
+    impl<USCI: SerialUsci> embedded_io::Write for Tx<USCI> {
+        /// Blocks until the first byte can be sent, then keeps writing bytes for as long as the Tx
+        /// buffer is ready without blocking.
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            let mut written = 0;
+            for &byte in buf {
+                if written > 0 && !usci.txifg_rd() {
+                    break;
+                }
+                while !usci.txifg_rd() {}
+                usci.tx_wr(byte);
+                written += 1;
+            }
+            Ok(written)
+        }
+
+        /// Blocks until the Tx buffer is empty. The FIFO may still be sending afterwards.
+        #[inline]
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            while !usci.txifg_rd() {}
+            Ok(())
+        }
+    }
+
+    impl<USCI: SerialUsci> WriteReady for Tx<USCI> {
+        #[inline]
+        fn write_ready(&mut self) -> Result<bool, Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            Ok(usci.txifg_rd())
+        }
+    }
+
+    impl<USCI: SerialUsci> ErrorType for Rx<USCI> {
+        type Error = RecvError;
+    }
+
+    impl<USCI: SerialUsci> embedded_io::Read for Rx<USCI> {
+        /// Blocks until the first byte is received, then keeps reading bytes for as long as they
+        /// are available without blocking. An erroneous byte received after the first one is left
+        /// in the Rx buffer, so that its error is returned by the next read.
+        #[inline]
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = nb::block!(Read::read(self))?;
+
+            let usci = unsafe { USCI::steal() };
+            let mut len = 1;
+            while len < buf.len() && usci.rxifg_rd() {
+                let statw = usci.statw_rd();
+                if statw.ucfe() || statw.ucpe() || statw.ucoe() {
+                    break;
+                }
+                buf[len] = usci.rx_rd();
+                len += 1;
+            }
+            Ok(len)
+        }
+    }
+
+    impl<USCI: SerialUsci> ReadReady for Rx<USCI> {
+        #[inline]
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            let usci = unsafe { USCI::steal() };
+            Ok(usci.rxifg_rd())
+        }
+    }
+
+    // Write errors are impossible, so the serial pair uses the receive error type
+    impl<USCI: SerialUsci> ErrorType for Serial<USCI> {
+        type Error = RecvError;
+    }
+
+    impl<USCI: SerialUsci> embedded_io::Read for Serial<USCI> {
+        #[inline]
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            embedded_io::Read::read(&mut self.rx, buf)
+        }
+    }
+
+    impl<USCI: SerialUsci> ReadReady for Serial<USCI> {
+        #[inline]
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            self.rx.read_ready()
+        }
+    }
+
+    impl<USCI: SerialUsci> embedded_io::Write for Serial<USCI> {
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let Ok(len) = embedded_io::Write::write(&mut self.tx, buf);
+            Ok(len)
+        }
+
+        #[inline]
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            let Ok(()) = embedded_io::Write::flush(&mut self.tx);
+            Ok(())
+        }
+    }
+
+    impl<USCI: SerialUsci> WriteReady for Serial<USCI> {
+        #[inline]
+        fn write_ready(&mut self) -> Result<bool, Self::Error> {
+            let Ok(ready) = self.tx.write_ready();
+            Ok(ready)
+        }
+    }
+}
@@ -7,15 +7,19 @@
 //! This module also contains traits used by other HAL modules that depend on TimerB, such as
 //! `Capture` and `Pwm`.
 
-use crate::clock::{Aclk, Smclk};
+use crate::calc::delay_chunk;
+use crate::clock::{Aclk, AclkSource, Clock, Smclk};
 use crate::gpio::{Alternate1, Floating, Input, Pin, Pin2, P6};
 use crate::hw_traits::timerb::{CCRn, Tbssel, TimerB};
 use core::marker::PhantomData;
 use embedded_hal::timer::{Cancel, CountDown, Periodic};
 use msp430fr247x as pac;
 
+pub mod wheel;
+
 pub use crate::hw_traits::timerb::{
-    TimerDiv, TimerExDiv, CCR0, CCR1, CCR2, CCR3, CCR4, CCR5, CCR6,
+    CompareGroup, CompareLoad, CounterLength, TimerDiv, TimerExDiv, CCR0, CCR1, CCR2, CCR3, CCR4,
+    CCR5, CCR6,
 };
 
 // Trait effectively sealed by CCRn
@@ -45,6 +49,34 @@ pub trait CapCmpTimer7:
 {
 }
 
+// Trait will not be used as a bound outside the HAL, so just keep it hidden
+#[doc(hidden)]
+pub trait CcrNum {
+    const NUM: usize;
+}
+
+impl CcrNum for CCR0 {
+    const NUM: usize = 0;
+}
+impl CcrNum for CCR1 {
+    const NUM: usize = 1;
+}
+impl CcrNum for CCR2 {
+    const NUM: usize = 2;
+}
+impl CcrNum for CCR3 {
+    const NUM: usize = 3;
+}
+impl CcrNum for CCR4 {
+    const NUM: usize = 4;
+}
+impl CcrNum for CCR5 {
+    const NUM: usize = 5;
+}
+impl CcrNum for CCR6 {
+    const NUM: usize = 6;
+}
+
 impl TimerPeriph for pac::TB0 {
     type Tbxclk = Pin<P6, Pin2, Alternate1<Input<Floating>>>;
 }
@@ -55,42 +87,55 @@ impl CapCmpTimer7 for pac::TB0 {}
 /// Used to configure `Timer`, `Capture`, and `Pwm`, which all use the TimerB peripheral.
 pub struct TimerConfig<T: TimerPeriph> {
     _timer: PhantomData<T>,
-    sel: Tbssel,
+    pub(crate) sel: Tbssel,
     div: TimerDiv,
     ex_div: TimerExDiv,
+    freq: u32,
+    pub(crate) cntl: CounterLength,
+    pub(crate) clgrp: CompareGroup,
 }
 
 impl<T: TimerPeriph> TimerConfig<T> {
     /// Configure timer clock source to ACLK
     #[inline]
-    pub fn aclk(_aclk: &Aclk) -> Self {
+    pub fn aclk<SRC: AclkSource>(aclk: &Aclk<SRC>) -> Self {
         TimerConfig {
             _timer: PhantomData,
             sel: Tbssel::Aclk,
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
+            freq: aclk.freq() as u32,
+            cntl: CounterLength::_16Bit,
+            clgrp: CompareGroup::Individual,
         }
     }
 
     /// Configure timer clock source to SMCLK
     #[inline]
-    pub fn smclk(_smclk: &Smclk) -> Self {
+    pub fn smclk(smclk: &Smclk) -> Self {
         TimerConfig {
             _timer: PhantomData,
             sel: Tbssel::Smclk,
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
+            freq: smclk.freq(),
+            cntl: CounterLength::_16Bit,
+            clgrp: CompareGroup::Individual,
         }
     }
 
-    /// Configure timer clock source to TBCLK
+    /// Configure timer clock source to TBCLK, passing in the appropriately configured pin used as
+    /// the clock signal as well as the frequency of the clock.
     #[inline]
-    pub fn tbclk(_pin: T::Tbxclk) -> Self {
+    pub fn tbclk(_pin: T::Tbxclk, freq: u32) -> Self {
         TimerConfig {
             _timer: PhantomData,
             sel: Tbssel::Tbxclk,
             div: TimerDiv::_1,
             ex_div: TimerExDiv::_1,
+            freq,
+            cntl: CounterLength::_16Bit,
+            clgrp: CompareGroup::Individual,
         }
     }
 
@@ -98,18 +143,50 @@ impl<T: TimerPeriph> TimerConfig<T> {
     #[inline]
     pub fn clk_div(self, div: TimerDiv, ex_div: TimerExDiv) -> Self {
         TimerConfig {
-            _timer: PhantomData,
-            sel: self.sel,
             div,
             ex_div,
+            ..self
         }
     }
 
+    /// Configure the length of the counter. Shorter counters wrap around sooner in continuous mode.
+    #[inline]
+    pub fn counter_length(self, cntl: CounterLength) -> Self {
+        TimerConfig { cntl, ..self }
+    }
+
+    /// Configure grouping of the compare latches, so that grouped capture-compare registers take
+    /// effect together. Mainly useful for PWM.
+    #[inline]
+    pub fn compare_group(self, clgrp: CompareGroup) -> Self {
+        TimerConfig { clgrp, ..self }
+    }
+
+    /// Frequency of the timer ticks, after both clock dividers are applied
+    #[inline]
+    pub fn tick_freq(&self) -> u32 {
+        let div = 1u32 << (self.div as u32);
+        let ex_div = (self.ex_div as u32) + 1;
+        self.freq / div / ex_div
+    }
+
+    /// Frequency of the timer clock source, before the clock dividers are applied
+    #[inline]
+    pub(crate) fn clk_freq(&self) -> u32 {
+        self.freq
+    }
+
+    /// Combined division of both clock dividers
+    #[inline]
+    pub(crate) fn total_div(&self) -> u32 {
+        (1u32 << (self.div as u32)) * ((self.ex_div as u32) + 1)
+    }
+
     #[inline]
     pub(crate) fn write_regs(self, timer: &T) {
         timer.reset();
         timer.set_tbidex(self.ex_div);
-        timer.config_clock(self.sel, self.div);
+        timer.config_clock(self.sel, self.div, self.cntl, self.clgrp);
     }
 }
 
@@ -129,9 +206,12 @@ impl<T: CapCmpTimer3> TimerParts3<T> {
     /// Create new set of timers out of a TBx peripheral
     #[inline(always)]
     pub fn new(_timer: T, config: TimerConfig<T>) -> Self {
+        let freq = config.tick_freq();
+        let cntl = config.cntl;
+        let sel = config.sel;
         config.write_regs(unsafe { &T::steal() });
         Self {
-            timer: Timer::new(),
+            timer: Timer::new(freq, cntl, sel),
             tbxiv: TBxIV(PhantomData),
             subtimer1: SubTimer::new(),
             subtimer2: SubTimer::new(),
@@ -163,9 +243,12 @@ impl<T: CapCmpTimer7> TimerParts7<T> {
     /// Create new set of timers out of a TBx peripheral
     #[inline(always)]
     pub fn new(_timer: T, config: TimerConfig<T>) -> Self {
+        let freq = config.tick_freq();
+        let cntl = config.cntl;
+        let sel = config.sel;
         config.write_regs(unsafe { &T::steal() });
         Self {
-            timer: Timer::new(),
+            timer: Timer::new(freq, cntl, sel),
             tbxiv: TBxIV(PhantomData),
             subtimer1: SubTimer::new(),
             subtimer2: SubTimer::new(),
@@ -178,11 +261,27 @@ impl<T: CapCmpTimer7> TimerParts7<T> {
 }
 
 /// Main periodic countdown timer
-pub struct Timer<T: TimerPeriph>(PhantomData<T>);
+pub struct Timer<T: TimerPeriph> {
+    _timer: PhantomData<T>,
+    freq: u32,
+    cntl: CounterLength,
+    sel: Tbssel,
+}
 
 impl<T: TimerPeriph> Timer<T> {
-    fn new() -> Self {
-        Self(PhantomData)
+    fn new(freq: u32, cntl: CounterLength, sel: Tbssel) -> Self {
+        Self {
+            _timer: PhantomData,
+            freq,
+            cntl,
+            sel,
+        }
+    }
+
+    /// Frequency of the timer ticks, as derived from the `TimerConfig` used to create the timer
+    #[inline(always)]
+    pub fn tick_freq(&self) -> u32 {
+        self.freq
     }
 }
 
@@ -218,6 +317,39 @@ pub enum TimerVector {
     MainTimer,
 }
 
+impl TimerVector {
+    // Number of the capture-compare register that caused the interrupt, if any
+    #[inline]
+    pub(crate) fn ccr_num(&self) -> Option<usize> {
+        match self {
+            TimerVector::SubTimer1 => Some(1),
+            TimerVector::SubTimer2 => Some(2),
+            TimerVector::SubTimer3 => Some(3),
+            TimerVector::SubTimer4 => Some(4),
+            TimerVector::SubTimer5 => Some(5),
+            TimerVector::SubTimer6 => Some(6),
+            TimerVector::NoInterrupt | TimerVector::MainTimer => None,
+        }
+    }
+}
+
+// Extend a count read from the timer with the overflows counted so far, such as by an interrupt
+// handler, including an overflow that's pending but hasn't been counted yet
+#[inline]
+pub(crate) fn extend_count<T: TimerB>(
+    timer: &T,
+    cntl: CounterLength,
+    count: u16,
+    overflows: u32,
+) -> u32 {
+    let overflows = if timer.tbifg_rd() && cntl.overflowed_before(count) {
+        overflows.wrapping_add(1)
+    } else {
+        overflows
+    };
+    cntl.extend(count, overflows)
+}
+
 #[inline]
 pub(crate) fn read_tbxiv<T: TimerB>(timer: &T) -> TimerVector {
     match timer.tbxiv_rd() {
@@ -282,7 +414,8 @@ impl<T: TimerPeriph + CapCmp<CCR0>> Cancel for Timer<T> {
 impl<T: TimerPeriph> Periodic for Timer<T> {}
 
 impl<T: TimerPeriph> Timer<T> {
-    /// Enable timer countdown expiration interrupts
+    /// Enable timer countdown expiration interrupts, which are also the overflow interrupts in
+    /// continuous mode
     #[inline(always)]
     pub fn enable_interrupts(&mut self) {
         let timer = unsafe { T::steal() };
@@ -295,6 +428,95 @@ impl<T: TimerPeriph> Timer<T> {
         let timer = unsafe { T::steal() };
         timer.tbie_clr();
     }
+
+    /// Length of the counter, as configured by the `TimerConfig` used to create the timer
+    #[inline(always)]
+    pub fn counter_length(&self) -> CounterLength {
+        self.cntl
+    }
+
+    /// Start the timer as a free-running counter in continuous mode. The counter counts up to the
+    /// maximum value of its length, then wraps to 0 and sets the overflow flag, which fires the
+    /// `TimerVector::MainTimer` interrupt if interrupts are enabled.
+    #[inline]
+    pub fn start_continuous(&mut self) {
+        let timer = unsafe { T::steal() };
+        timer.stop();
+        timer.continuous();
+    }
+
+    /// Current value of the counter
+    #[inline]
+    pub fn count(&self) -> u16 {
+        let timer = unsafe { T::steal() };
+        // SMCLK is derived from the same source as MCLK, so the counter can be read directly
+        if self.sel == Tbssel::Smclk {
+            return timer.tbxr_rd();
+        }
+        // Reads can be corrupted if the timer clock is asynchronous to MCLK, so take the majority
+        // of up to three reads. Waiting for two consecutive reads to agree could spin forever on
+        // a timer clock close to MCLK.
+        let first = timer.tbxr_rd();
+        let second = timer.tbxr_rd();
+        if first == second {
+            return second;
+        }
+        let third = timer.tbxr_rd();
+        if third == first {
+            first
+        } else {
+            // Either the second read agrees, or the counter moved between every read and the
+            // latest value is the best guess
+            third
+        }
+    }
+
+    /// Check whether the counter has overflowed, clearing the overflow flag if so
+    #[inline]
+    pub fn take_overflow(&mut self) -> bool {
+        let timer = unsafe { T::steal() };
+        if timer.tbifg_rd() {
+            timer.tbifg_clr();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Read the counter extended in software by an overflow count, for free-running counters
+    /// polled without interrupts. Pending overflows are added to `overflows`. Must be called at
+    /// least twice per counter period to keep track of all overflows.
+    pub fn extended_count(&mut self, overflows: &mut u32) -> u32 {
+        let count = self.count();
+        if self.take_overflow() {
+            *overflows = overflows.wrapping_add(1);
+            if !self.cntl.overflowed_before(count) {
+                return self.cntl.extend(count, overflows.wrapping_sub(1));
+            }
+        }
+        self.cntl.extend(count, *overflows)
+    }
+
+    /// Block for the specified number of timer ticks. Tick counts that don't fit in the timer
+    /// period are handled by chaining multiple countdowns. The timer is stopped afterwards. The
+    /// shortest possible delay is 2 ticks.
+    #[inline]
+    pub fn delay_ticks(&mut self, mut ticks: u32) {
+        while ticks > 0 {
+            ticks = self.start_delay_chunk(ticks);
+            nb::block!(self.wait()).ok();
+        }
+        self.cancel().ok();
+    }
+
+    // Start the next countdown of a delay and return the ticks remaining after it
+    #[inline]
+    pub(crate) fn start_delay_chunk(&mut self, ticks: u32) -> u32 {
+        let chunk = delay_chunk(ticks, self.cntl.max() as u32 + 1);
+        // The countdown period is one tick longer than the count written to CCR0
+        self.start((chunk - 1) as u16);
+        ticks.saturating_sub(chunk)
+    }
 }
 
 impl<T: CapCmp<C>, C> SubTimer<T, C> {
@@ -335,3 +557,19 @@ impl<T: CapCmp<C>, C> SubTimer<T, C> {
         timer.ccie_clr();
     }
 }
+
+#[cfg(feature = "embedded-hal-1")]
+mod ehal1 {
+    use super::*;
+    use embedded_hal_1::delay::DelayNs;
+
+    impl<T: TimerPeriph> DelayNs for Timer<T> {
+        /// Delays are rounded up to the nearest timer tick, so the resolution of the delay depends
+        /// on the clock frequency of the timer.
+        #[inline]
+        fn delay_ns(&mut self, ns: u32) {
+            let ticks = (ns as u64 * self.freq as u64).div_ceil(1_000_000_000);
+            self.delay_ticks(ticks as u32);
+        }
+    }
+}
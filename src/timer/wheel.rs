@@ -0,0 +1,197 @@
+//! Software timers multiplexed on a single sub-timer
+//!
+//! `TimerWheel` runs the main timer in continuous mode as a free-running 32-bit system tick,
+//! extended in software by counting overflows, and multiplexes a statically sized number of
+//! one-shot and periodic software timers on one sub-timer compare channel. The compare register
+//! is always programmed to the next deadline, so the CPU can sleep between expirations.
+//!
+//! Both the overflow and the sub-timer interrupts fire on the `TIMERx_B1` vector. The handler
+//! reads the interrupt vector and passes it to `TimerWheel::handle`, which dispatches the
+//! callbacks of expired timers from within the handler. Callbacks only ever run from `handle`:
+//! starting or cancelling a timer whose deadline already passed leaves it to the interrupt.
+
+use super::{extend_count, CapCmp, CcrNum, SubTimer, Timer, TimerPeriph, TimerVector};
+use crate::hw_traits::timerb::CCRn;
+
+/// Callback invoked from the interrupt handler when a software timer expires
+pub type Callback = fn(TimerId);
+
+/// Identifier of a running software timer. Each timer started gets a new generation, so a stale
+/// identifier doesn't refer to a newer timer reusing its slot, unless 65536 more timers were
+/// started in between.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimerId {
+    slot: usize,
+    generation: u16,
+}
+
+/// Error returned when all software timer slots are in use
+#[derive(Debug)]
+pub struct WheelFull;
+
+#[derive(Clone, Copy)]
+struct Slot {
+    deadline: u32,
+    // `None` for one-shot timers
+    period: Option<u32>,
+    callback: Callback,
+    generation: u16,
+}
+
+/// Software timer wheel with `N` timer slots, running on the main timer and sub-timer `C`
+pub struct TimerWheel<T: TimerPeriph + CapCmp<C>, C: CcrNum, const N: usize> {
+    timer: Timer<T>,
+    _subtimer: SubTimer<T, C>,
+    overflows: u32,
+    slots: [Option<Slot>; N],
+    generation: u16,
+}
+
+impl<T: TimerPeriph + CapCmp<C>, C: CcrNum, const N: usize> TimerWheel<T, C, N> {
+    /// Create the timer wheel, starting the main timer in continuous mode and enabling the
+    /// overflow and sub-timer interrupts. Ticks are timer ticks, so the tick rate is set by the
+    /// `TimerConfig` used to create the timer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sub-timer uses CCR0, whose interrupt doesn't go through the interrupt vector.
+    pub fn new(mut timer: Timer<T>, subtimer: SubTimer<T, C>) -> Self {
+        assert!(C::NUM != 0);
+        let periph = unsafe { T::steal() };
+        CCRn::<C>::ccie_clr(&periph);
+        CCRn::<C>::ccifg_clr(&periph);
+        timer.start_continuous();
+        timer.enable_interrupts();
+        CCRn::<C>::ccie_set(&periph);
+        TimerWheel {
+            timer,
+            _subtimer: subtimer,
+            overflows: 0,
+            slots: [None; N],
+            generation: 0,
+        }
+    }
+
+    /// Current value of the system tick
+    #[inline]
+    pub fn now(&self) -> u32 {
+        let count = self.timer.count();
+        let periph = unsafe { T::steal() };
+        extend_count(&periph, self.timer.counter_length(), count, self.overflows)
+    }
+
+    /// Start a one-shot timer that expires after `ticks`
+    #[inline]
+    pub fn start_oneshot(&mut self, ticks: u32, callback: Callback) -> Result<TimerId, WheelFull> {
+        self.start(ticks, None, callback)
+    }
+
+    /// Start a periodic timer that expires every `ticks`. Deadlines are computed from the previous
+    /// deadline rather than the time the callback ran, so the period doesn't drift.
+    #[inline]
+    pub fn start_periodic(&mut self, ticks: u32, callback: Callback) -> Result<TimerId, WheelFull> {
+        self.start(ticks, Some(ticks.max(1)), callback)
+    }
+
+    fn start(
+        &mut self,
+        ticks: u32,
+        period: Option<u32>,
+        callback: Callback,
+    ) -> Result<TimerId, WheelFull> {
+        let idx = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_none())
+            .ok_or(WheelFull)?;
+        self.generation = self.generation.wrapping_add(1);
+        self.slots[idx] = Some(Slot {
+            deadline: self.now().wrapping_add(ticks),
+            period,
+            callback,
+            generation: self.generation,
+        });
+        self.reprogram(false);
+        Ok(TimerId {
+            slot: idx,
+            generation: self.generation,
+        })
+    }
+
+    /// Stop a timer. Returns false if the timer already expired or was stopped.
+    #[inline]
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        if !self.is_running(id) {
+            return false;
+        }
+        self.slots[id.slot] = None;
+        self.reprogram(false);
+        true
+    }
+
+    /// Check whether a timer is still running
+    #[inline]
+    pub fn is_running(&self, id: TimerId) -> bool {
+        matches!(self.slots[id.slot], Some(slot) if slot.generation == id.generation)
+    }
+
+    /// Handle a timer interrupt, as read from the interrupt vector. Counts overflows, dispatches
+    /// the callbacks of expired timers, and reprograms the sub-timer to the next deadline. Vectors
+    /// belonging to other sub-timers are ignored.
+    pub fn handle(&mut self, vector: &TimerVector) {
+        match vector {
+            TimerVector::MainTimer => self.overflows = self.overflows.wrapping_add(1),
+            vector if vector.ccr_num() == Some(C::NUM) => (),
+            _ => return,
+        }
+        self.reprogram(true);
+    }
+
+    // Run all expired timers if `dispatch` is set, then set the compare register to the earliest
+    // deadline. If that deadline passes before the compare register is set, its match would be
+    // missed, so the expired timers are run again, or left to the interrupt if not dispatching.
+    fn reprogram(&mut self, dispatch: bool) {
+        let periph = unsafe { T::steal() };
+        loop {
+            let now = self.now();
+            // Earliest deadline and the ticks remaining until it
+            let mut next: Option<(u32, u32)> = None;
+            for (idx, entry) in self.slots.iter_mut().enumerate() {
+                let Some(slot) = entry else { continue };
+                // Deadlines up to half the tick range in the past count as expired
+                if dispatch && (now.wrapping_sub(slot.deadline) as i32) >= 0 {
+                    let id = TimerId {
+                        slot: idx,
+                        generation: slot.generation,
+                    };
+                    (slot.callback)(id);
+                    match slot.period {
+                        Some(period) => slot.deadline = slot.deadline.wrapping_add(period),
+                        None => {
+                            *entry = None;
+                            continue;
+                        }
+                    }
+                }
+                // Periodic timers that are still behind after catching up one period are due now
+                let remaining = (slot.deadline.wrapping_sub(now) as i32).max(0) as u32;
+                if next.is_none_or(|(_, next_remaining)| remaining < next_remaining) {
+                    next = Some((slot.deadline, remaining));
+                }
+            }
+
+            let Some((deadline, _)) = next else { return };
+            // Deadlines beyond the current counter period match early, which just causes another
+            // pass through here
+            let max = self.timer.counter_length().max();
+            CCRn::<C>::set_ccrn(&periph, deadline as u16 & max);
+            if (self.now().wrapping_sub(deadline) as i32) < 0 {
+                return;
+            }
+            if !dispatch {
+                CCRn::<C>::ccifg_set(&periph);
+                return;
+            }
+        }
+    }
+}
@@ -3,16 +3,23 @@
 //! **Note**: MSP430 devices will reset after bootup if watchdog is not stopped after an initial 32
 //! ms interval (roughly). If this is undesirable, call `Wdt::constrain()` as soon in the
 //! application as possible to stop the watchdog.
+//!
+//! The watchdog keeps track of the frequency of its clock source, so timeouts can be given in
+//! milliseconds with `Wdt::start_timeout` and `Wdt::set_period`, as well as in raw clock periods.
 
-use crate::clock::{Aclk, Smclk};
+use crate::calc::{wdt_setting, VLOCLK};
+use crate::clock::{Aclk, AclkSource, Clock, Smclk};
 use core::marker::PhantomData;
 use embedded_hal::timer::{Cancel, CountDown, Periodic};
 use embedded_hal::watchdog::{Watchdog, WatchdogDisable, WatchdogEnable};
 use msp430fr247x as pac;
 use pac::wdt_a::wdtctl::WDTSSEL_A;
 
+pub mod supervisor;
+
 const PASSWORD: u8 = 0x5A;
 
+pub use crate::calc::OutOfRange;
 pub use pac::wdt_a::wdtctl::WDTIS_A as WdtClkPeriods;
 
 mod sealed {
@@ -28,6 +35,7 @@ mod sealed {
 pub struct Wdt<MODE> {
     _mode: PhantomData<MODE>,
     periph: pac::WDT_A,
+    clk_freq: u32,
 }
 
 impl Wdt<WatchdogMode> {
@@ -45,6 +53,7 @@ impl Wdt<WatchdogMode> {
         Wdt {
             _mode: PhantomData,
             periph: wdt,
+            clk_freq: VLOCLK as u32,
         }
     }
 }
@@ -84,7 +93,8 @@ impl<MODE: WatchdogSelect> Wdt<MODE> {
     }
 
     #[inline]
-    fn set_clk(&mut self, clk_src: WDTSSEL_A) -> &mut Self {
+    fn set_clk(&mut self, clk_src: WDTSSEL_A, clk_freq: u32) -> &mut Self {
+        self.clk_freq = clk_freq;
         // Halt timer first, as specified in the user's guide
         self.periph.wdtctl.write(|w| {
             Self::prewrite(w, 0)
@@ -107,20 +117,43 @@ impl<MODE: WatchdogSelect> Wdt<MODE> {
 
     /// Set watchdog clock source to ACLK and halt timer.
     #[inline]
-    pub fn set_aclk(&mut self, _clks: &Aclk) -> &mut Self {
-        self.set_clk(WDTSSEL_A::ACLK)
+    pub fn set_aclk<SRC: AclkSource>(&mut self, clks: &Aclk<SRC>) -> &mut Self {
+        self.set_clk(WDTSSEL_A::ACLK, clks.freq() as u32)
     }
 
     /// Set watchdog clock source to VLOCLK and halt timer.
     #[inline]
     pub fn set_vloclk(&mut self) -> &mut Self {
-        self.set_clk(WDTSSEL_A::VLOCLK)
+        self.set_clk(WDTSSEL_A::VLOCLK, VLOCLK as u32)
     }
 
     /// Set watchdog clock source to SMCLK and halt timer.
     #[inline]
-    pub fn set_smclk(&mut self, _clks: &Smclk) -> &mut Self {
-        self.set_clk(WDTSSEL_A::SMCLK)
+    pub fn set_smclk(&mut self, clks: &Smclk) -> &mut Self {
+        self.set_clk(WDTSSEL_A::SMCLK, clks.freq())
+    }
+
+    /// Frequency of the selected clock source
+    #[inline]
+    pub fn clk_freq(&self) -> u32 {
+        self.clk_freq
+    }
+
+    // Interval of the selected clock source closest to a timeout, and the actual timeout in ms
+    #[inline]
+    fn periods_for(&self, timeout_ms: u32) -> Result<(WdtClkPeriods, u32), OutOfRange> {
+        let setting = wdt_setting(self.clk_freq, timeout_ms)?;
+        let periods = match setting.wdtis {
+            0 => WdtClkPeriods::_2G,
+            1 => WdtClkPeriods::_128M,
+            2 => WdtClkPeriods::_8192K,
+            3 => WdtClkPeriods::_512K,
+            4 => WdtClkPeriods::_32K,
+            5 => WdtClkPeriods::_8192,
+            6 => WdtClkPeriods::_512,
+            _ => WdtClkPeriods::_64,
+        };
+        Ok((periods, setting.timeout_ms))
     }
 
     // Reset countdown, unpause timer, and set timeout in a single write
@@ -167,6 +200,18 @@ impl WatchdogEnable for Wdt<WatchdogMode> {
     }
 }
 
+impl Wdt<WatchdogMode> {
+    /// Start the watchdog with the interval of the selected clock source closest to `timeout_ms`
+    /// milliseconds. Returns the actual timeout in milliseconds, or an error without starting the
+    /// watchdog if the timeout is out of range for the clock source.
+    #[inline]
+    pub fn start_timeout(&mut self, timeout_ms: u32) -> Result<u32, OutOfRange> {
+        let (periods, actual_ms) = self.periods_for(timeout_ms)?;
+        self.unpause_and_set_time(periods);
+        Ok(actual_ms)
+    }
+}
+
 impl WatchdogDisable for Wdt<WatchdogMode> {
     #[inline]
     fn disable(&mut self) {
@@ -219,6 +264,7 @@ impl Wdt<WatchdogMode> {
         let mut wdt = Wdt {
             _mode: PhantomData,
             periph: self.periph,
+            clk_freq: self.clk_freq,
         };
         // Change mode bit and pause timer
         wdt.pause();
@@ -233,6 +279,7 @@ impl Wdt<IntervalMode> {
         let mut wdt = Wdt {
             _mode: PhantomData,
             periph: self.periph,
+            clk_freq: self.clk_freq,
         };
         // Change mode bit and pause timer
         wdt.pause();
@@ -242,6 +289,16 @@ impl Wdt<IntervalMode> {
         wdt
     }
 
+    /// Start the interval timer with the interval of the selected clock source closest to
+    /// `period_ms` milliseconds. Returns the actual period in milliseconds, or an error without
+    /// starting the timer if the period is out of range for the clock source.
+    #[inline]
+    pub fn set_period(&mut self, period_ms: u32) -> Result<u32, OutOfRange> {
+        let (periods, actual_ms) = self.periods_for(period_ms)?;
+        self.unpause_and_set_time(periods);
+        Ok(actual_ms)
+    }
+
     /// Enable interrupts for watchdog, which fires when the watchdog interrupt flag is set in
     /// interval mode. This setting does nothing in watchdog mode, but will carry over when
     /// switching to interval mode.
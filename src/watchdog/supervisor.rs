@@ -0,0 +1,117 @@
+//! Task supervision on top of the watchdog
+//!
+//! `Supervisor` owns the watchdog in watchdog mode and only feeds it while every registered task
+//! keeps sending heartbeats within its deadline. Deadlines are counted in calls to
+//! `Supervisor::service`, which must be called at a fixed rate, faster than the watchdog period.
+//! Tasks can also be given a window, in which case a heartbeat arriving too early is a fault as
+//! well.
+//!
+//! Once a task faults the watchdog is no longer fed, and the task and the cause are written to
+//! backup memory word `BAKMEM15`, which is retained through the resulting reset. The record can be
+//! read after reboot with `last_starvation`. The application must not use `BAKMEM15` otherwise.
+
+use super::{OutOfRange, WatchdogMode, Wdt, WdtClkPeriods};
+use embedded_hal::watchdog::{Watchdog, WatchdogEnable};
+use msp430fr247x as pac;
+
+mod deadlines;
+
+use deadlines::Deadlines;
+pub use deadlines::{Cause, Health, Starvation, SupervisorFull, TaskId};
+
+/// Watchdog supervisor with `N` task slots
+pub struct Supervisor<const N: usize> {
+    wdt: Wdt<WatchdogMode>,
+    deadlines: Deadlines<N>,
+}
+
+impl<const N: usize> Supervisor<N> {
+    /// Create the supervisor with no registered tasks. The watchdog is not started until
+    /// `start` is called.
+    #[inline]
+    pub fn new(wdt: Wdt<WatchdogMode>) -> Self {
+        Supervisor {
+            wdt,
+            deadlines: Deadlines::new(),
+        }
+    }
+
+    /// Release the watchdog
+    #[inline]
+    pub fn free(self) -> Wdt<WatchdogMode> {
+        self.wdt
+    }
+
+    /// Register a task that must send a heartbeat at least once every `max` service calls
+    #[inline]
+    pub fn register(&mut self, max: u16) -> Result<TaskId, SupervisorFull> {
+        self.register_window(0, max)
+    }
+
+    /// Register a task whose heartbeats must be between `min` and `max` service calls apart. The
+    /// first heartbeat after registering only has to arrive within `max` service calls.
+    #[inline]
+    pub fn register_window(&mut self, min: u16, max: u16) -> Result<TaskId, SupervisorFull> {
+        self.deadlines.register_window(min, max)
+    }
+
+    /// Stop supervising a task
+    #[inline]
+    pub fn unregister(&mut self, id: TaskId) {
+        self.deadlines.unregister(id);
+    }
+
+    /// Start the watchdog with the specified timeout, which must be longer than the interval
+    /// between service calls
+    #[inline]
+    pub fn start(&mut self, periods: WdtClkPeriods) {
+        self.wdt.start(periods);
+    }
+
+    /// Start the watchdog with the timeout closest to `timeout_ms` milliseconds. Returns the actual
+    /// timeout in milliseconds, or an error if the timeout is out of range for the clock source.
+    #[inline]
+    pub fn start_timeout(&mut self, timeout_ms: u32) -> Result<u32, OutOfRange> {
+        self.wdt.start_timeout(timeout_ms)
+    }
+
+    /// Record a heartbeat of a task
+    #[inline]
+    pub fn heartbeat(&mut self, id: TaskId) {
+        self.deadlines.heartbeat(id);
+    }
+
+    /// Check the deadlines of all tasks and feed the watchdog if none of them faulted. Must be
+    /// called at a fixed rate. After a fault the watchdog is never fed again.
+    pub fn service(&mut self) -> Health {
+        let health = self.deadlines.service();
+        match health {
+            Health::Starved(starvation) => write_record(starvation),
+            _ => self.wdt.feed(),
+        }
+        health
+    }
+}
+
+#[inline]
+fn write_record(starvation: Starvation) {
+    let bkmem = unsafe { &*pac::BKMEM::ptr() };
+    bkmem
+        .bakmem15
+        .write(|w| unsafe { w.bits(starvation.to_record()) });
+}
+
+/// Read the record of the task that starved before the last reset, if any. The record stays in
+/// backup memory until cleared with `clear_starvation`.
+#[inline]
+pub fn last_starvation() -> Option<Starvation> {
+    let bkmem = unsafe { &*pac::BKMEM::ptr() };
+    Starvation::from_record(bkmem.bakmem15.read().bits())
+}
+
+/// Clear the starvation record
+#[inline]
+pub fn clear_starvation() {
+    let bkmem = unsafe { &*pac::BKMEM::ptr() };
+    bkmem.bakmem15.write(|w| unsafe { w.bits(0) });
+}
@@ -0,0 +1,178 @@
+//! Deadline bookkeeping of the supervisor, kept apart from the watchdog and backup memory so it
+//! can be tested on the host
+
+// Marks a valid record in the upper byte of the backup memory word, since its contents are
+// undefined after power-up
+const RECORD_MAGIC: u16 = 0xA500;
+const RECORD_EARLY: u16 = 0x0080;
+const RECORD_TASK_MASK: u16 = 0x007F;
+
+/// Identifier of a supervised task
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TaskId(u8);
+
+impl TaskId {
+    /// Index of the task, in order of registration
+    #[inline]
+    pub fn index(self) -> u8 {
+        self.0
+    }
+}
+
+/// Error returned when all task slots are in use
+#[derive(Debug)]
+pub struct SupervisorFull;
+
+/// Why a task faulted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cause {
+    /// No heartbeat arrived before the task's deadline
+    Missed,
+    /// A heartbeat arrived before the task's window opened
+    Early,
+}
+
+/// Task that caused a watchdog reset, and why
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Starvation {
+    /// Faulting task
+    pub task: TaskId,
+    /// Cause of the fault
+    pub cause: Cause,
+}
+
+impl Starvation {
+    /// Encode as the record written to backup memory
+    #[inline]
+    pub fn to_record(self) -> u16 {
+        let early = match self.cause {
+            Cause::Missed => 0,
+            Cause::Early => RECORD_EARLY,
+        };
+        RECORD_MAGIC | early | self.task.0 as u16
+    }
+
+    /// Decode a record read from backup memory, if it holds one
+    #[inline]
+    pub fn from_record(bits: u16) -> Option<Self> {
+        if bits & !(RECORD_EARLY | RECORD_TASK_MASK) != RECORD_MAGIC {
+            return None;
+        }
+        let cause = if bits & RECORD_EARLY != 0 {
+            Cause::Early
+        } else {
+            Cause::Missed
+        };
+        Some(Starvation {
+            task: TaskId((bits & RECORD_TASK_MASK) as u8),
+            cause,
+        })
+    }
+}
+
+/// Result of servicing the supervisor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Health {
+    /// All tasks checked in on time, the watchdog was fed
+    Ok,
+    /// Early warning: a task has used up more than half of its deadline. The watchdog was still
+    /// fed.
+    Late(TaskId),
+    /// A task faulted. The watchdog is no longer fed and will reset the device.
+    Starved(Starvation),
+}
+
+#[derive(Clone, Copy)]
+struct Task {
+    min: u16,
+    max: u16,
+    // Service calls since the last heartbeat, or since registering
+    elapsed: u16,
+    // Whether a heartbeat arrived since registering, which opens the window for the next one
+    beaten: bool,
+    early: bool,
+}
+
+// Deadlines of the tasks registered in `N` slots
+pub struct Deadlines<const N: usize> {
+    tasks: [Option<Task>; N],
+    starved: Option<Starvation>,
+}
+
+impl<const N: usize> Deadlines<N> {
+    #[inline]
+    pub fn new() -> Self {
+        Deadlines {
+            tasks: [None; N],
+            starved: None,
+        }
+    }
+
+    pub fn register_window(&mut self, min: u16, max: u16) -> Result<TaskId, SupervisorFull> {
+        let idx = self
+            .tasks
+            .iter()
+            .take(RECORD_TASK_MASK as usize + 1)
+            .position(|task| task.is_none())
+            .ok_or(SupervisorFull)?;
+        self.tasks[idx] = Some(Task {
+            min,
+            max: max.max(1),
+            elapsed: 0,
+            beaten: false,
+            early: false,
+        });
+        Ok(TaskId(idx as u8))
+    }
+
+    #[inline]
+    pub fn unregister(&mut self, id: TaskId) {
+        self.tasks[id.0 as usize] = None;
+    }
+
+    // The window is only checked between two heartbeats, since the first one after registering
+    // can come at any point of the task's cycle
+    #[inline]
+    pub fn heartbeat(&mut self, id: TaskId) {
+        if let Some(task) = self.tasks[id.0 as usize].as_mut() {
+            if task.beaten && task.elapsed < task.min {
+                task.early = true;
+            }
+            task.beaten = true;
+            task.elapsed = 0;
+        }
+    }
+
+    // Count a service call and check every task. Once a task faulted, returns `Starved` on this
+    // and every later call.
+    pub fn service(&mut self) -> Health {
+        if let Some(starvation) = self.starved {
+            return Health::Starved(starvation);
+        }
+
+        let mut health = Health::Ok;
+        for (idx, entry) in self.tasks.iter_mut().enumerate() {
+            let Some(task) = entry else { continue };
+            let task_id = TaskId(idx as u8);
+            task.elapsed = task.elapsed.saturating_add(1);
+            let cause = if task.early {
+                Cause::Early
+            } else if task.elapsed > task.max {
+                Cause::Missed
+            } else {
+                if task.elapsed > task.max / 2 && health == Health::Ok {
+                    health = Health::Late(task_id);
+                }
+                continue;
+            };
+
+            let starvation = Starvation {
+                task: task_id,
+                cause,
+            };
+            self.starved = Some(starvation);
+            return Health::Starved(starvation);
+        }
+        health
+    }
+}